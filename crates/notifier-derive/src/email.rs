@@ -0,0 +1,191 @@
+use std::path::PathBuf;
+
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    Error, Ident, LitStr, Token,
+};
+
+struct Part {
+    name: Ident,
+    path: LitStr,
+}
+
+impl Parse for Part {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let path = input.parse()?;
+
+        Ok(Self { name, path })
+    }
+}
+
+/// The template's parts, each given as a path to its file.
+pub struct Input {
+    subject: LitStr,
+    html: LitStr,
+    text: Option<LitStr>,
+}
+
+impl Parse for Input {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let parts = Punctuated::<Part, Token![,]>::parse_terminated(input)?;
+
+        let mut subject = None;
+        let mut html = None;
+        let mut text = None;
+
+        for Part { name, path } in parts {
+            let part = match name.to_string().as_str() {
+                "subject" => &mut subject,
+                "html" => &mut html,
+                "text" => &mut text,
+                _ => {
+                    return Err(Error::new_spanned(
+                        name,
+                        "expected `subject`, `html` or `text`",
+                    ))
+                }
+            };
+
+            if part.replace(path).is_some() {
+                return Err(Error::new_spanned(name, "the part is given more than once"));
+            }
+        }
+
+        Ok(Self {
+            subject: subject.ok_or_else(|| input.error("missing the `subject` template"))?,
+            html: html.ok_or_else(|| input.error("missing the `html` template"))?,
+            text,
+        })
+    }
+}
+
+pub fn expand(input: Input) -> syn::Result<proc_macro2::TokenStream> {
+    let subject = read(&input.subject)?;
+    validate_liquid(subject.trim_end()).map_err(|e| Error::new_spanned(&input.subject, e))?;
+
+    let html = read(&input.html)?;
+    let html = compile_mjml(&html).map_err(|e| Error::new_spanned(&input.html, e))?;
+
+    let text = match &input.text {
+        Some(path) => {
+            let text = read(path)?;
+            validate_liquid(text.trim_end()).map_err(|e| Error::new_spanned(path, e))?;
+            Some(text)
+        }
+        None => None,
+    };
+
+    let subject = subject.trim_end();
+    let text = match text {
+        Some(text) => {
+            let text = text.trim_end();
+            quote!(::core::option::Option::Some(#text))
+        }
+        None => quote!(::core::option::Option::None),
+    };
+
+    // rebuild when a file changes
+    let paths = [Some(&input.subject), Some(&input.html), input.text.as_ref()];
+    let tracked = paths.into_iter().flatten().map(|path| {
+        quote! {
+            const _: &str = ::core::include_str!(::core::concat!(
+                ::core::env!("CARGO_MANIFEST_DIR"),
+                "/",
+                #path
+            ));
+        }
+    });
+
+    Ok(quote! {
+        {
+            #(#tracked)*
+
+            ::notifier_email::EmailTemplate {
+                subject: #subject,
+                html: ::notifier::template::Markup::Html(#html),
+                text: #text,
+            }
+        }
+    })
+}
+
+fn read(path: &LitStr) -> syn::Result<String> {
+    let mut file = std::env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_default();
+    file.push(path.value());
+
+    std::fs::read_to_string(&file)
+        .map_err(|e| Error::new_spanned(path, format!("failed to read {}: {e}", file.display())))
+}
+
+/// Parse the template as Liquid, like `TemplateEngine::register`.
+fn validate_liquid(template: &str) -> Result<(), String> {
+    let parser = liquid::ParserBuilder::with_stdlib()
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    parser
+        .parse(template)
+        .map(|_| ())
+        .map_err(|e| format!("invalid liquid template: {e}"))
+}
+
+/// Compile the MJML like `Markup::parse` and check that the resulting HTML
+/// parses as Liquid, like `TemplateEngine::register`.
+fn compile_mjml(template: &str) -> Result<String, String> {
+    let html = mrml::mjml::MJML::parse(template)
+        .map_err(|e| format!("invalid mjml template: {e:?}"))?
+        .render(&mrml::prelude::render::Options::default())
+        .map_err(|e| format!("failed to render the mjml template: {e:?}"))?;
+
+    validate_liquid(&html)?;
+
+    Ok(html)
+}
+
+#[cfg(test)]
+mod test_email_template {
+    use super::*;
+
+    #[test]
+    fn test_validates_liquid() {
+        assert!(validate_liquid("Hello {{ name }}").is_ok());
+
+        let error = validate_liquid("Hello {{ name").unwrap_err();
+        assert!(error.starts_with("invalid liquid template"), "{error}");
+    }
+
+    #[test]
+    fn test_compiles_mjml() {
+        let mjml = "<mjml><mj-body><mj-text>Hello {{ name }}</mj-text></mj-body></mjml>";
+        let html = compile_mjml(mjml).unwrap();
+        assert!(html.starts_with("<!doctype html>"), "{html}");
+        assert!(html.contains("Hello {{ name }}"));
+
+        let error = compile_mjml("<mjml><mj-body>").unwrap_err();
+        assert!(error.starts_with("invalid mjml template"), "{error}");
+
+        let error = compile_mjml("<mjml><mj-body><mj-text>{% if %}</mj-text></mj-body></mjml>")
+            .unwrap_err();
+        assert!(error.starts_with("invalid liquid template"), "{error}");
+    }
+
+    #[test]
+    fn test_expands_to_compiled_html() {
+        let input: Input = syn::parse_quote!(
+            subject = "tests/templates/welcome.subject",
+            html = "tests/templates/welcome.mjml",
+        );
+
+        let expanded = expand(input).unwrap().to_string();
+
+        assert!(expanded.contains("Markup :: Html"), "{expanded}");
+        assert!(expanded.contains("<!doctype html>"), "{expanded}");
+        assert!(!expanded.contains("<mjml>"), "{expanded}");
+    }
+}
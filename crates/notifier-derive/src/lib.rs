@@ -0,0 +1,238 @@
+//! `#[derive(Notification)]` for the `notifier` crate, re-exported by it with
+//! the `derive` feature, and `email_template!` for `notifier-email`,
+//! re-exported by it with the `macros` feature.
+
+#[cfg(feature = "email")]
+mod email;
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{
+    parenthesized, parse_macro_input, punctuated::Punctuated, DeriveInput, Error, Ident, LitStr,
+    Token,
+};
+
+struct Template {
+    channel: LitStr,
+    part: LitStr,
+    path: LitStr,
+}
+
+#[derive(Default)]
+struct Attributes {
+    id: Option<LitStr>,
+    channels: Vec<LitStr>,
+    templates: Vec<Template>,
+}
+
+/// Implement `notifier::Notification` with a `&'static str` id.
+///
+/// ```ignore
+/// #[derive(Serialize, Notification)]
+/// #[notification(
+///     id = "welcome",
+///     channels("email"),
+///     template(
+///         channel = "email",
+///         subject = "templates/welcome.subject",
+///         html = "templates/welcome.mjml",
+///     ),
+/// )]
+/// struct Welcome {
+///     name: String,
+/// }
+/// ```
+///
+/// Each `template` embeds the parts of a channel's template, named by their
+/// keys. The paths are relative to the crate's manifest and are embedded with
+/// `include_str!`. Their channels are added to the notification's channels.
+///
+/// Deriving two notifications with the same id in a crate fails to compile:
+/// each id defines a hidden macro at the crate's root, which can only be
+/// defined once.
+#[proc_macro_derive(Notification, attributes(notification))]
+pub fn derive_notification(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Build a `notifier_email::EmailTemplate` from template files, checked while
+/// compiling.
+///
+/// ```ignore
+/// let template = email_template!(
+///     subject = "templates/welcome.subject",
+///     html = "templates/welcome.mjml",
+///     text = "templates/welcome.txt",
+/// );
+/// ```
+///
+/// The paths are relative to the crate's manifest and `text` is optional. The
+/// Liquid is parsed and the MJML is compiled during the build, so a syntax
+/// error fails compilation instead of registering the template. The template
+/// holds the compiled HTML, so the MJML isn't compiled again when it's
+/// registered.
+#[cfg(feature = "email")]
+#[proc_macro]
+pub fn email_template(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as email::Input);
+
+    email::expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let attributes = parse_attributes(&input)?;
+
+    let id = attributes.id.ok_or_else(|| {
+        Error::new_spanned(
+            &input.ident,
+            "missing the notification's id: #[notification(id = \"...\")]",
+        )
+    })?;
+
+    let mut channels = attributes.channels;
+    for template in &attributes.templates {
+        if !channels
+            .iter()
+            .any(|c| c.value() == template.channel.value())
+        {
+            channels.push(template.channel.clone());
+        }
+    }
+
+    let templates = attributes.templates.iter().map(|template| {
+        let Template {
+            channel,
+            part,
+            path,
+        } = template;
+
+        quote! {
+            ::notifier::EmbeddedTemplate {
+                channel: #channel,
+                part: #part,
+                source: ::core::include_str!(::core::concat!(
+                    ::core::env!("CARGO_MANIFEST_DIR"),
+                    "/",
+                    #path
+                )),
+            }
+        }
+    });
+
+    let id_macro = id_macro(&id);
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::notifier::Notification for #ident #ty_generics #where_clause {
+            type Id = &'static str;
+
+            fn id() -> Self::Id {
+                #id
+            }
+
+            fn channels() -> &'static [&'static str] {
+                &[#(#channels),*]
+            }
+
+            fn templates() -> &'static [::notifier::EmbeddedTemplate] {
+                &[#(#templates),*]
+            }
+        }
+
+        // fails to compile when another notification in the crate has the id
+        #[doc(hidden)]
+        #[allow(non_local_definitions)]
+        #[macro_export]
+        macro_rules! #id_macro {
+            () => {};
+        }
+    })
+}
+
+/// The name of the macro defined for the id.
+fn id_macro(id: &LitStr) -> Ident {
+    let name = id
+        .value()
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' => c.to_string(),
+            _ => format!("_{:x}_", c as u32),
+        })
+        .collect::<String>();
+
+    Ident::new(
+        &format!("__notifier_notification_id_{name}"),
+        proc_macro2::Span::call_site(),
+    )
+}
+
+fn parse_attributes(input: &DeriveInput) -> syn::Result<Attributes> {
+    let mut attributes = Attributes::default();
+
+    for attr in &input.attrs {
+        if !attr.path().is_ident("notification") {
+            continue;
+        }
+
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("id") {
+                attributes.id = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("channels") {
+                let content;
+                parenthesized!(content in meta.input);
+
+                let channels = Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?;
+                attributes.channels.extend(channels);
+            } else if meta.path.is_ident("template") {
+                attributes.templates.extend(parse_template(&meta)?);
+            } else {
+                return Err(meta.error("expected `id`, `channels` or `template`"));
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(attributes)
+}
+
+fn parse_template(meta: &syn::meta::ParseNestedMeta) -> syn::Result<Vec<Template>> {
+    let mut channel = None;
+    let mut parts = Vec::new();
+
+    meta.parse_nested_meta(|nested| {
+        let value = nested.value()?.parse::<LitStr>()?;
+
+        if nested.path.is_ident("channel") {
+            channel = Some(value);
+        } else {
+            let part = nested
+                .path
+                .get_ident()
+                .ok_or_else(|| nested.error("expected the name of the template's part"))?;
+
+            parts.push((LitStr::new(&part.to_string(), part.span()), value));
+        }
+
+        Ok(())
+    })?;
+
+    let channel = channel.ok_or_else(|| meta.error("a template needs a `channel`"))?;
+
+    Ok(parts
+        .into_iter()
+        .map(|(part, path)| Template {
+            channel: channel.clone(),
+            part,
+            path,
+        })
+        .collect())
+}
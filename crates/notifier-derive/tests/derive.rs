@@ -0,0 +1,52 @@
+use notifier::{EmbeddedTemplate, Notification};
+use serde::Serialize;
+
+#[derive(Serialize, Notification)]
+#[notification(id = "password_reset")]
+struct PasswordReset {
+    token: String,
+}
+
+#[derive(Serialize, Notification)]
+#[notification(
+    id = "welcome",
+    channels("sms"),
+    template(
+        channel = "email",
+        subject = "tests/templates/welcome.subject",
+        html = "tests/templates/welcome.mjml"
+    )
+)]
+struct Welcome {
+    name: String,
+}
+
+#[test]
+fn test_derives_id() {
+    assert_eq!(PasswordReset::id(), "password_reset");
+    assert!(PasswordReset::channels().is_empty());
+    assert!(PasswordReset::templates().is_empty());
+}
+
+#[test]
+fn test_derives_channels_and_templates() {
+    assert_eq!(Welcome::id(), "welcome");
+    assert_eq!(Welcome::channels(), &["sms", "email"]);
+
+    assert_eq!(
+        Welcome::templates()[0],
+        EmbeddedTemplate {
+            channel: "email",
+            part: "subject",
+            source: "Welcome, {{ name }}!",
+        }
+    );
+    assert!(Welcome::templates()[1]
+        .source
+        .contains("<mj-text>Welcome, {{ name }}!</mj-text>"));
+}
+
+#[test]
+fn test_duplicate_ids_fail_to_compile() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
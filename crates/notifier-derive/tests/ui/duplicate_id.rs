@@ -0,0 +1,16 @@
+use notifier::Notification;
+use serde::Serialize;
+
+#[derive(Serialize, Notification)]
+#[notification(id = "welcome")]
+struct Welcome;
+
+mod other {
+    use super::*;
+
+    #[derive(Serialize, Notification)]
+    #[notification(id = "welcome")]
+    struct Onboarding;
+}
+
+fn main() {}
@@ -1,13 +1,20 @@
+// lets `email_template!` refer to the crate by name in its own tests
+extern crate self as notifier_email;
+
 use async_trait::async_trait;
-use notifier::{template::TemplateService, Channel, Error, Id, Provider};
+use notifier::{template::TemplateService, Channel, DeliveryReceipt, Error, Id, Provider};
 
 pub mod contact;
 pub mod message;
 pub mod provider;
 pub mod template;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
 pub use contact::EmailAddress;
 pub use message::{EmailContents, EmailMessage};
+#[cfg(feature = "macros")]
+pub use notifier_derive::email_template;
 use provider::EmailProvider;
 pub use template::EmailTemplate;
 use template::RegisteredEmailTemplate;
@@ -34,6 +41,9 @@ pub struct EmailChannel {
 }
 
 impl EmailChannel {
+    /// The key that the channel is registered with.
+    pub const KEY: &'static str = "email";
+
     pub fn new(provider: impl Provider<Message = EmailMessage>, options: Options) -> Self {
         Self {
             provider: EmailProvider::new(provider),
@@ -41,14 +51,17 @@ impl EmailChannel {
         }
     }
 
-    pub async fn send(&self, message: EmailMessage) -> Result<(), Error> {
-        self.provider.send(message).await?;
-        Ok(())
+    pub async fn send(&self, message: EmailMessage) -> Result<DeliveryReceipt, Error> {
+        let receipt =
+            notifier::provider::send_instrumented(self.provider.as_ref(), message).await?;
+        Ok(receipt)
     }
 }
 
 #[async_trait]
 impl<I: Id + 'static> Channel<I> for EmailChannel {
+    const KEY: &'static str = EmailChannel::KEY;
+
     type Contact = EmailAddress;
     type Message = EmailMessage;
     type RenderedTemplate = EmailContents;
@@ -68,7 +81,7 @@ impl<I: Id + 'static> Channel<I> for EmailChannel {
     }
 
     /// Send a message using the channel's provider
-    async fn send(&self, message: Self::Message) -> Result<(), Error> {
+    async fn send(&self, message: Self::Message) -> Result<DeliveryReceipt, Error> {
         self.send(message).await
     }
 
@@ -97,14 +110,14 @@ impl<I: Id + 'static> Channel<I> for EmailChannel {
 
         let channel_type = <Self as Channel<I>>::channel_type(self);
 
-        template_service.register_template(notification_id, channel_type, Box::new(template));
+        template_service.register_template(notification_id, channel_type, template);
 
         Ok(())
     }
 
     fn render_template(
         &self,
-        notification_id: I,
+        notification_id: &I,
         context: &notifier::template::engine::RenderContext,
         template_service: &TemplateService<I>,
     ) -> Result<Self::RenderedTemplate, Error> {
@@ -132,12 +145,21 @@ impl<I: Id + 'static> Channel<I> for EmailChannel {
 #[cfg(test)]
 mod test {
     use indoc::indoc;
-    use notifier::{template::Markup, Notification, Notifier};
+    use notifier::{template::Markup, testing::RecordingProvider, Notification, Notifier};
     use serde::{Deserialize, Serialize};
 
-    use super::{provider::test::TestProvider, *};
-
-    #[derive(Serialize, Deserialize)]
+    use super::{testing::EmailAssertions, *};
+
+    #[derive(Serialize, Deserialize, Notification)]
+    #[notification(
+        id = "foo_notification",
+        template(
+            channel = "email",
+            subject = "templates/hello.subject",
+            html = "templates/hello.mjml",
+            text = "templates/hello.txt"
+        )
+    )]
     pub struct HelloNotification {
         name: String,
     }
@@ -148,16 +170,8 @@ mod test {
         }
     }
 
-    impl Notification for HelloNotification {
-        type Id = &'static str;
-
-        fn id() -> Self::Id {
-            "foo_notification"
-        }
-    }
-
-    fn create_notifier(provider: TestProvider) -> Notifier<&'static str> {
-        let mut notifier = Notifier::new();
+    fn create_notifier(provider: RecordingProvider<EmailMessage>) -> Notifier<&'static str> {
+        let notifier = Notifier::new();
 
         let channel = EmailChannel::new(
             provider,
@@ -185,7 +199,7 @@ mod test {
             text: Some("Hello, {{ name }}!"),
         };
 
-        notifier.register_channel(channel);
+        notifier.register_channel(channel).unwrap();
 
         notifier
             .register_notification::<HelloNotification, EmailTemplate>(email_template)
@@ -204,30 +218,28 @@ mod test {
 
     #[tokio::test]
     async fn test_sets_default_sender() {
-        let provider = TestProvider::default();
+        let provider = RecordingProvider::new();
         let notifier = create_notifier(provider.clone());
 
         let contact = EmailAddress::new("recipient@test.com", None);
         let notification = HelloNotification::new("World".to_owned());
 
-        notifier
+        let receipt = notifier
             .send_message_to_contact(notification, contact)
             .await
             .unwrap();
 
-        let message = provider
-            .0
-            .lock()
-            .unwrap()
-            .pop()
-            .expect("message wasn't added to test provider correctly");
+        assert_eq!(receipt.provider_id(), "recording");
+
+        let message = provider.assert_one_email("recipient@test.com", "Hello");
 
         assert_eq!(message.from(), &EmailAddress::new("sender@test.com", None));
+        provider.assert_no_email_to("sender@test.com");
     }
 
     #[tokio::test]
     async fn test_sets_default_reply_to() {
-        let provider = TestProvider::default();
+        let provider = RecordingProvider::new();
         let notifier = create_notifier(provider.clone());
 
         let contact = EmailAddress::new("recipient@test.com", None);
@@ -238,12 +250,7 @@ mod test {
             .await
             .unwrap();
 
-        let message = provider
-            .0
-            .lock()
-            .unwrap()
-            .pop()
-            .expect("message wasn't added to test provider correctly");
+        let message = provider.assert_one_email("recipient@test.com", "Hello");
 
         assert_eq!(
             message.reply_to(),
@@ -253,7 +260,7 @@ mod test {
 
     #[tokio::test]
     async fn test_renders_email_message() {
-        let provider = TestProvider::default();
+        let provider = RecordingProvider::new();
         let notifier = create_notifier(provider.clone());
 
         let contact = EmailAddress::new("recipient@test.com", None);
@@ -264,12 +271,7 @@ mod test {
             .await
             .unwrap();
 
-        let message = provider
-            .0
-            .lock()
-            .unwrap()
-            .pop()
-            .expect("message wasn't added to test provider correctly");
+        let message = provider.assert_one_email("recipient@test.com", "Hello");
 
         assert_eq!(message.contents().subject(), "Hello, World!");
         assert_eq!(message.contents().text(), Some(&"Hello, World!".to_owned()));
@@ -277,4 +279,79 @@ mod test {
         let html_contents = include_str!("../snapshots/expected_html_output.txt");
         pretty_assertions::assert_eq!(html_contents, message.contents().html());
     }
+
+    #[tokio::test]
+    async fn test_renders_embedded_template() {
+        let provider = RecordingProvider::new();
+        let notifier = Notifier::new();
+
+        notifier
+            .register_channel(EmailChannel::new(
+                provider.clone(),
+                Options::new(EmailAddress::new("sender@test.com", None), None),
+            ))
+            .unwrap();
+
+        let template = EmailTemplate::embedded::<HelloNotification>().unwrap();
+
+        notifier
+            .register_notification::<HelloNotification, EmailTemplate>(template)
+            .unwrap();
+
+        notifier
+            .send_message_to_contact(
+                HelloNotification::new("World".to_owned()),
+                EmailAddress::new("recipient@test.com", None),
+            )
+            .await
+            .unwrap();
+
+        let message = provider.assert_one_email("recipient@test.com", "Hello, World!");
+
+        assert_eq!(message.contents().subject(), "Hello, World!");
+        assert_eq!(message.contents().text(), Some(&"Hello, World!".to_owned()));
+
+        let html_contents = include_str!("../snapshots/expected_html_output.txt");
+        pretty_assertions::assert_eq!(html_contents, message.contents().html());
+    }
+
+    #[tokio::test]
+    async fn test_registers_compiled_template() {
+        let provider = RecordingProvider::new();
+        let notifier = Notifier::new();
+
+        notifier
+            .register_channel(EmailChannel::new(
+                provider.clone(),
+                Options::new(EmailAddress::new("sender@test.com", None), None),
+            ))
+            .unwrap();
+
+        let template = notifier_derive::email_template!(
+            subject = "templates/hello.subject",
+            html = "templates/hello.mjml",
+            text = "templates/hello.txt",
+        );
+
+        assert_eq!(template.subject, "Hello, {{ name }}!");
+
+        notifier
+            .register_notification::<HelloNotification, EmailTemplate>(template)
+            .unwrap();
+
+        notifier
+            .send_message_to_contact(
+                HelloNotification::new("World".to_owned()),
+                EmailAddress::new("recipient@test.com", None),
+            )
+            .await
+            .unwrap();
+
+        let message = provider.assert_one_email("recipient@test.com", "Hello, World!");
+
+        assert_eq!(message.contents().text(), Some(&"Hello, World!".to_owned()));
+
+        let html_contents = include_str!("../snapshots/expected_html_output.txt");
+        pretty_assertions::assert_eq!(html_contents, message.contents().html());
+    }
 }
@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::EmailAddress;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmailContents {
     subject: String,
     html: String,
@@ -49,7 +49,7 @@ impl EmailContents {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmailMessage {
     to: EmailAddress,
     from: EmailAddress,
@@ -7,8 +7,6 @@ use crate::EmailMessage;
 #[cfg(feature = "smtp")]
 pub mod smtp;
 
-pub mod test;
-
 pub struct EmailProvider(Box<dyn Provider<Message = EmailMessage>>);
 
 impl EmailProvider {
@@ -1,9 +1,16 @@
 use async_trait::async_trait;
 use lettre::{
     message::{Mailbox, MultiPart, SinglePart},
+    transport::smtp::{
+        response::{Code, Response},
+        Error as SmtpError,
+    },
     AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
 };
-use notifier::{provider::Error, Provider};
+use notifier::{
+    provider::{Error, ErrorKind},
+    DeliveryReceipt, Provider,
+};
 
 use crate::{EmailAddress, EmailMessage};
 
@@ -16,7 +23,7 @@ impl SmtpProvider {
         Self { transport }
     }
 
-    pub async fn send(&self, message: EmailMessage) -> Result<(), Error> {
+    pub async fn send(&self, message: EmailMessage) -> Result<DeliveryReceipt, Error> {
         let from: Mailbox = message.from().clone().try_into()?;
 
         let to: Mailbox = message.to().clone().try_into()?;
@@ -42,17 +49,64 @@ impl SmtpProvider {
             context: Some("failed to build the lettre::Message"),
         })?;
 
-        self.transport.send(email).await.map_err(|e| Error::Send {
+        let response = self.transport.send(email).await.map_err(|e| Error::Send {
+            kind: error_kind(&e),
             source: e.into(),
             channel_id: "email",
             provider_id: "smtp",
             context: Some("SMTP email provider failed to send the email"),
         })?;
 
-        Ok(())
+        Ok(receipt_from_response(&response))
+    }
+}
+
+/// Classify the error by the server's reply code. Errors without a reply, such
+/// as connection failures, are transient unless they're caused by the client.
+fn error_kind(error: &SmtpError) -> ErrorKind {
+    match error.status() {
+        Some(code) => error_kind_from_code(code),
+        None if error.is_client() => ErrorKind::Permanent,
+        None => ErrorKind::Transient,
+    }
+}
+
+fn error_kind_from_code(code: Code) -> ErrorKind {
+    let code = code.to_string().parse::<u16>().unwrap_or_default();
+
+    match code {
+        // service not available, usually sent when the server is throttling
+        421 => ErrorKind::RateLimited { retry_after: None },
+        400..=499 => ErrorKind::Transient,
+        // mailbox unavailable, user not local or mailbox name not allowed
+        550 | 551 | 553 => ErrorKind::InvalidRecipient,
+        500..=599 => ErrorKind::Permanent,
+        _ => ErrorKind::Transient,
     }
 }
 
+/// Create the receipt from the server's reply to the message. Servers usually
+/// include the queue id in the reply, e.g. `250 2.0.0 Ok: queued as 4B1F2`.
+fn receipt_from_response(response: &Response) -> DeliveryReceipt {
+    let message = response.message().collect::<Vec<_>>().join("\n");
+
+    let receipt = DeliveryReceipt::new("smtp")
+        .with_metadata("code", response.code().to_string())
+        .with_metadata("message", message);
+
+    match queue_id(response) {
+        Some(queue_id) => receipt.with_provider_message_id(queue_id),
+        None => receipt,
+    }
+}
+
+fn queue_id(response: &Response) -> Option<&str> {
+    response.message().find_map(|line| {
+        let (_, queue_id) = line.split_once("queued as ")?;
+        queue_id.split_whitespace().next()
+    })
+}
+
 impl TryFrom<EmailAddress> for Mailbox {
     type Error = Error;
 
@@ -81,7 +135,70 @@ impl Provider for SmtpProvider {
         "smtp"
     }
 
-    async fn send(&self, message: Self::Message) -> Result<(), Error> {
+    async fn send(&self, message: Self::Message) -> Result<DeliveryReceipt, Error> {
         self.send(message).await
     }
 }
+
+#[cfg(test)]
+mod test_smtp_provider {
+    use lettre::transport::smtp::response::{Category, Detail, Severity};
+
+    use super::*;
+
+    #[test]
+    fn test_error_kind_from_code() {
+        let code = |severity, category, detail| Code::new(severity, category, detail);
+
+        assert_eq!(
+            error_kind_from_code(code(
+                Severity::TransientNegativeCompletion,
+                Category::Connections,
+                Detail::One
+            )),
+            ErrorKind::RateLimited { retry_after: None }
+        );
+        assert_eq!(
+            error_kind_from_code(code(
+                Severity::TransientNegativeCompletion,
+                Category::MailSystem,
+                Detail::One
+            )),
+            ErrorKind::Transient
+        );
+        assert_eq!(
+            error_kind_from_code(code(
+                Severity::PermanentNegativeCompletion,
+                Category::MailSystem,
+                Detail::Zero
+            )),
+            ErrorKind::InvalidRecipient
+        );
+        assert_eq!(
+            error_kind_from_code(code(
+                Severity::PermanentNegativeCompletion,
+                Category::MailSystem,
+                Detail::Four
+            )),
+            ErrorKind::Permanent
+        );
+    }
+
+    #[test]
+    fn test_receipt_has_queue_id() {
+        let response = Response::new(
+            Code::new(
+                Severity::PositiveCompletion,
+                Category::MailSystem,
+                Detail::Zero,
+            ),
+            vec!["2.0.0 Ok: queued as 4B1F2C0A1D".to_owned()],
+        );
+
+        let receipt = receipt_from_response(&response);
+
+        assert_eq!(receipt.provider_id(), "smtp");
+        assert_eq!(receipt.provider_message_id(), Some("4B1F2C0A1D"));
+        assert_eq!(receipt.metadata()["code"], "250");
+    }
+}
@@ -1,4 +1,9 @@
-use notifier::template::{Markup, TemplateId};
+use notifier::{
+    template::{Markup, RegisteredTemplate, TemplateId},
+    Notification,
+};
+
+use crate::EmailChannel;
 
 pub struct EmailTemplate<'a> {
     /// The template for the email's subject.
@@ -9,6 +14,26 @@ pub struct EmailTemplate<'a> {
     pub text: Option<&'a str>,
 }
 
+impl EmailTemplate<'static> {
+    /// Build the template from the parts embedded in the notification for the
+    /// `email` channel: the `subject`, the `html` as MJML and the optional
+    /// `text`. Returns `None` when the subject or HTML isn't embedded.
+    pub fn embedded<N: Notification>() -> Option<Self> {
+        let part = |name: &str| {
+            N::templates()
+                .iter()
+                .find(|template| template.channel == EmailChannel::KEY && template.part == name)
+                .map(|template| template.source)
+        };
+
+        Some(Self {
+            subject: part("subject")?.trim_end(),
+            html: Markup::Mjml(part("html")?),
+            text: part("text").map(str::trim_end),
+        })
+    }
+}
+
 // impl<'a> RegisterTemplate for EmailTemplate<'a> {
 //     type Template = RegisteredEmailTemplate;
 
@@ -37,6 +62,15 @@ pub struct RegisteredEmailTemplate {
     pub(crate) text: Option<TemplateId>,
 }
 
+impl RegisteredTemplate for RegisteredEmailTemplate {
+    fn template_ids(&self) -> Vec<TemplateId> {
+        [Some(self.subject), Some(self.html), self.text]
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+}
+
 // impl RenderTemplate for RegisteredEmailTemplate {
 //     type Message = EmailContents;
 
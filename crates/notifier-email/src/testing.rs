@@ -0,0 +1,41 @@
+//! Assertions for the emails recorded by a
+//! [`RecordingProvider`](notifier::testing::RecordingProvider), enabled with
+//! the `testing` feature.
+
+use notifier::testing::RecordingProvider;
+
+use crate::EmailMessage;
+
+pub trait EmailAssertions {
+    /// The recorded emails sent to the address.
+    fn emails_to(&self, email: &str) -> Vec<EmailMessage>;
+
+    /// Assert that exactly one email was sent to the address with a subject
+    /// containing the text, and return it.
+    fn assert_one_email(&self, to: &str, subject_contains: &str) -> EmailMessage;
+
+    /// Assert that no emails were sent to the address.
+    fn assert_no_email_to(&self, to: &str);
+}
+
+impl EmailAssertions for RecordingProvider<EmailMessage> {
+    fn emails_to(&self, email: &str) -> Vec<EmailMessage> {
+        self.matching(|message| message.to().email() == email)
+    }
+
+    #[track_caller]
+    fn assert_one_email(&self, to: &str, subject_contains: &str) -> EmailMessage {
+        self.assert_one(
+            &format!("to {to} with a subject containing {subject_contains:?}"),
+            |message| {
+                message.to().email() == to
+                    && message.contents().subject().contains(subject_contains)
+            },
+        )
+    }
+
+    #[track_caller]
+    fn assert_no_email_to(&self, to: &str) {
+        self.assert_none(&format!("to {to}"), |message| message.to().email() == to)
+    }
+}
@@ -0,0 +1,415 @@
+use std::{
+    any::{Any, TypeId},
+    fmt,
+};
+
+use crate::{channel::registry::ChannelInstance, Channel, Error, Id, Notification, Notifier};
+
+/// A problem with the notifier's registrations, found when it is built.
+#[derive(Debug, thiserror::Error)]
+pub enum Problem {
+    #[error("Failed to register the channel {key}: {source}")]
+    Channel {
+        key: &'static str,
+        #[source]
+        source: Error,
+    },
+
+    #[error("Failed to register the template for the notification {notification_id}: {source}")]
+    Template {
+        notification_id: String,
+        #[source]
+        source: Error,
+    },
+
+    #[error(
+        "The notification {notification} has the id {notification_id} of another notification"
+    )]
+    DuplicateId {
+        notification_id: String,
+        notification: &'static str,
+    },
+
+    #[error("The notification {notification_id} doesn't have a template for any channel")]
+    MissingTemplate { notification_id: String },
+
+    #[error("The notification {notification_id} requires the channel {channel}, which hasn't been registered")]
+    MissingChannel {
+        notification_id: String,
+        channel: &'static str,
+    },
+
+    #[error(
+        "The notification {notification_id} doesn't have a template for the channel {channel}"
+    )]
+    NotCovered {
+        notification_id: String,
+        channel: &'static str,
+    },
+
+    #[error("The notification {notification_id} is sent with the instance {instance} of the channel {channel}, which hasn't been registered")]
+    MissingInstance {
+        notification_id: String,
+        channel: &'static str,
+        instance: &'static str,
+    },
+}
+
+/// Every problem found while building the notifier.
+#[derive(Debug)]
+pub struct BuildError {
+    pub problems: Vec<Problem>,
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The notifier has {} invalid registrations",
+            self.problems.len()
+        )?;
+
+        for problem in &self.problems {
+            write!(f, "\n  - {problem}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Builds a [`Notifier`], collecting the problems with its channels and
+/// templates so that they're reported together when it is built, rather than
+/// when a notification is sent.
+pub struct NotifierBuilder<I: Id> {
+    notifier: Notifier<I>,
+    /// The declared notifications, with the type that declared them.
+    declared: Vec<(I, TypeId)>,
+    required: Vec<(I, &'static str)>,
+    /// How each declared notification picks its channels' instances.
+    instances: Vec<(I, ChannelInstance)>,
+    problems: Vec<Problem>,
+}
+
+impl<I: Id> Default for NotifierBuilder<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I: Id> NotifierBuilder<I> {
+    pub fn new() -> Self {
+        Self {
+            notifier: Notifier::new(),
+            declared: Vec::new(),
+            required: Vec::new(),
+            instances: Vec::new(),
+            problems: Vec::new(),
+        }
+    }
+}
+
+impl<I: Id> NotifierBuilder<I> {
+    /// Register the channel as its type's default instance.
+    pub fn channel<C: Channel<I>>(mut self, channel: C) -> Self {
+        if let Err(source) = self.notifier.register_channel(channel) {
+            self.problems.push(Problem::Channel {
+                key: C::KEY,
+                source,
+            });
+        }
+        self
+    }
+
+    /// Register the channel as a named instance of its type.
+    pub fn channel_instance<C: Channel<I>>(mut self, name: &'static str, channel: C) -> Self {
+        if let Err(source) = self.notifier.register_channel_instance(name, channel) {
+            self.problems.push(Problem::Channel {
+                key: C::KEY,
+                source,
+            });
+        }
+        self
+    }
+
+    /// Register the notification's template for the template's channel, and
+    /// declare the notification.
+    pub fn template<N: Notification<Id = I>, T: Any>(mut self, template: T) -> Self {
+        match self.notifier.register_notification::<N, T>(template) {
+            Ok(()) => self.declare::<N>(),
+            Err(source) => {
+                self.problems.push(Problem::Template {
+                    notification_id: N::id().to_string(),
+                    source,
+                });
+                self
+            }
+        }
+    }
+
+    /// Declare that the notification is sent, requiring a template for it on
+    /// at least one channel and on each of [`Notification::channels`], and
+    /// its [`Notification::channel_instance`] on each of its channels. Another
+    /// notification declared with the same id is reported.
+    pub fn declare<N: Notification<Id = I>>(mut self) -> Self {
+        let declared = self.declared.iter().find(|(id, _)| *id == N::id());
+
+        match declared {
+            Some((_, type_id)) if *type_id != TypeId::of::<N>() => {
+                self.problems.push(Problem::DuplicateId {
+                    notification_id: N::id().to_string(),
+                    notification: std::any::type_name::<N>(),
+                });
+            }
+            Some(_) => {}
+            None => {
+                self.declared.push((N::id(), TypeId::of::<N>()));
+
+                for channel in N::channels() {
+                    self.required.push((N::id(), *channel));
+                }
+                self.instances.push((N::id(), N::channel_instance));
+            }
+        }
+        self
+    }
+
+    /// Require a template for the notification on the channel with the key.
+    pub fn require<N: Notification<Id = I>>(mut self, channel: &'static str) -> Self {
+        self = self.declare::<N>();
+
+        if !self.required.contains(&(N::id(), channel)) {
+            self.required.push((N::id(), channel));
+        }
+        self
+    }
+
+    /// Configure the rest of the notifier, e.g. its stores and middleware.
+    pub fn configure(mut self, configure: impl FnOnce(&mut Notifier<I>)) -> Self {
+        configure(&mut self.notifier);
+        self
+    }
+
+    /// Validate the registrations and build the notifier, failing with every
+    /// problem that was found.
+    pub fn build(self) -> Result<Notifier<I>, BuildError> {
+        let mut problems = self.problems;
+
+        {
+            let channels = self.notifier.channels.read().unwrap();
+            let templates = self.notifier.templates.read().unwrap();
+
+            for (notification_id, _) in &self.declared {
+                if !templates.has_notification(notification_id) {
+                    problems.push(Problem::MissingTemplate {
+                        notification_id: notification_id.to_string(),
+                    });
+                }
+            }
+
+            for (notification_id, channel) in &self.required {
+                match channels.find_type_by_key(channel) {
+                    None => problems.push(Problem::MissingChannel {
+                        notification_id: notification_id.to_string(),
+                        channel,
+                    }),
+                    Some(channel_type)
+                        if !templates.has_template(notification_id, channel_type) =>
+                    {
+                        problems.push(Problem::NotCovered {
+                            notification_id: notification_id.to_string(),
+                            channel,
+                        })
+                    }
+                    Some(_) => {}
+                }
+            }
+
+            for (notification_id, instance_of) in &self.instances {
+                for channel_type in templates.notification_channels(notification_id) {
+                    let Some(instance) = instance_of(channel_type) else {
+                        continue;
+                    };
+
+                    if channels.get(channel_type, Some(instance)).is_none() {
+                        problems.push(Problem::MissingInstance {
+                            notification_id: notification_id.to_string(),
+                            channel: channel_type.key(),
+                            instance,
+                        });
+                    }
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(self.notifier)
+        } else {
+            Err(BuildError { problems })
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_notifier_builder {
+    use super::*;
+    use crate::channel::ChannelType;
+    use crate::testing::{TestChannel, TestNotification, TestTemplate};
+
+    #[derive(serde::Serialize)]
+    struct TestUndeclaredNotification;
+
+    impl Notification for TestUndeclaredNotification {
+        type Id = &'static str;
+
+        fn id() -> Self::Id {
+            "test_undeclared"
+        }
+    }
+
+    #[derive(serde::Serialize)]
+    struct TestSmsNotification;
+
+    impl Notification for TestSmsNotification {
+        type Id = &'static str;
+
+        fn id() -> Self::Id {
+            "test_sms"
+        }
+
+        fn channels() -> &'static [&'static str] {
+            &["sms"]
+        }
+    }
+
+    #[test]
+    fn test_requires_notification_channels() {
+        let error = NotifierBuilder::<&'static str>::new()
+            .channel(TestChannel::default())
+            .declare::<TestSmsNotification>()
+            .build()
+            .err()
+            .unwrap();
+
+        assert!(matches!(
+            error.problems.as_slice(),
+            [
+                Problem::MissingTemplate { .. },
+                Problem::MissingChannel { channel: "sms", .. },
+            ]
+        ));
+    }
+
+    #[derive(serde::Serialize)]
+    struct TestMarketingNotification;
+
+    impl Notification for TestMarketingNotification {
+        type Id = &'static str;
+
+        fn id() -> Self::Id {
+            "test_marketing"
+        }
+
+        fn channel_instance(_channel: ChannelType) -> Option<&'static str> {
+            Some("marketing")
+        }
+    }
+
+    #[test]
+    fn test_requires_notification_channel_instance() {
+        let error = NotifierBuilder::<&'static str>::new()
+            .channel(TestChannel::default())
+            .template::<TestMarketingNotification, _>(TestTemplate("message"))
+            .build()
+            .err()
+            .unwrap();
+
+        assert!(matches!(
+            error.problems.as_slice(),
+            [Problem::MissingInstance {
+                channel: "test",
+                instance: "marketing",
+                ..
+            }]
+        ));
+
+        let notifier = NotifierBuilder::<&'static str>::new()
+            .channel_instance("marketing", TestChannel::default())
+            .template::<TestMarketingNotification, _>(TestTemplate("message"))
+            .build();
+
+        assert!(notifier.is_ok());
+    }
+
+    #[derive(serde::Serialize)]
+    struct TestDuplicateNotification;
+
+    impl Notification for TestDuplicateNotification {
+        type Id = &'static str;
+
+        fn id() -> Self::Id {
+            TestNotification::id()
+        }
+    }
+
+    #[test]
+    fn test_reports_duplicate_ids() {
+        let error = NotifierBuilder::<&'static str>::new()
+            .channel(TestChannel::default())
+            .template::<TestNotification, _>(TestTemplate("message = {{message}}"))
+            .template::<TestNotification, _>(TestTemplate("message = {{message}}"))
+            .template::<TestDuplicateNotification, _>(TestTemplate("duplicate"))
+            .build()
+            .err()
+            .unwrap();
+
+        assert!(matches!(
+            error.problems.as_slice(),
+            [Problem::DuplicateId { notification, .. }]
+                if notification.ends_with("TestDuplicateNotification")
+        ));
+    }
+
+    #[test]
+    fn test_builds_valid_notifier() {
+        let notifier = NotifierBuilder::<&'static str>::new()
+            .channel(TestChannel::default())
+            .template::<TestNotification, _>(TestTemplate("message = {{message}}"))
+            .require::<TestNotification>("test")
+            .build();
+
+        assert!(notifier.is_ok());
+    }
+
+    #[test]
+    fn test_reports_every_problem() {
+        let error = NotifierBuilder::<&'static str>::new()
+            .channel(TestChannel::default())
+            .channel(TestChannel::default())
+            .template::<TestNotification, _>(TestTemplate("message = {{message"))
+            .declare::<TestUndeclaredNotification>()
+            .require::<TestUndeclaredNotification>("failing")
+            .require::<TestUndeclaredNotification>("test")
+            .build()
+            .err()
+            .unwrap();
+
+        assert!(matches!(
+            error.problems.as_slice(),
+            [
+                Problem::Channel { key: "test", .. },
+                Problem::Template { .. },
+                Problem::MissingTemplate { .. },
+                Problem::MissingChannel {
+                    channel: "failing",
+                    ..
+                },
+                Problem::NotCovered {
+                    channel: "test",
+                    ..
+                },
+            ]
+        ));
+    }
+}
@@ -1,4 +1,7 @@
-use std::any::{Any, TypeId};
+use std::{
+    any::{Any, TypeId},
+    fmt,
+};
 
 use async_trait::async_trait;
 
@@ -7,21 +10,39 @@ pub mod registry;
 use crate::{
     contact::{Contact, DynContact},
     message::{DynMessage, DynMessageContents, Message},
+    receipt::DeliveryReceipt,
     template::{engine::RenderContext, TemplateService},
     Error, Id,
 };
 
+/// Identifies a channel by its stable key, e.g. "email". The key is used in
+/// errors, logs, metrics, persisted messages and configuration, so it
+/// shouldn't change between releases.
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
-pub struct ChannelType(TypeId);
+pub struct ChannelType(&'static str);
 
 impl ChannelType {
-    pub fn of<M: Message, C: Contact>() -> Self {
-        Self(TypeId::of::<(M, C)>())
+    pub const fn new(key: &'static str) -> Self {
+        Self(key)
+    }
+
+    pub fn key(&self) -> &'static str {
+        self.0
+    }
+}
+
+impl fmt::Display for ChannelType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
     }
 }
 
 #[async_trait]
 pub trait Channel<I: Id>: Any + Sync + Send {
+    /// The channel's stable key, which must be unique among the registered
+    /// channels.
+    const KEY: &'static str;
+
     /// The recipient of a message.
     type Contact: Contact;
 
@@ -38,7 +59,7 @@ pub trait Channel<I: Id>: Any + Sync + Send {
 
     /// Unique ID for the channel.
     fn channel_type(&self) -> ChannelType {
-        ChannelType::of::<Self::Message, Self::Contact>()
+        ChannelType::new(Self::KEY)
     }
 
     /// Create a message that has the contact as the recipient
@@ -49,7 +70,7 @@ pub trait Channel<I: Id>: Any + Sync + Send {
     ) -> Result<Self::Message, Error>;
 
     /// Send a message using the channel's provider
-    async fn send(&self, message: Self::Message) -> Result<(), Error>;
+    async fn send(&self, message: Self::Message) -> Result<DeliveryReceipt, Error>;
 
     /// Register a user's template with the template service.
     fn register_template(
@@ -62,7 +83,7 @@ pub trait Channel<I: Id>: Any + Sync + Send {
     /// Render the template using the template service.
     fn render_template(
         &self,
-        notification_id: I,
+        notification_id: &I,
         context: &RenderContext,
         template_service: &TemplateService<I>,
     ) -> Result<Self::RenderedTemplate, Error>;
@@ -77,8 +98,20 @@ pub trait Channel<I: Id>: Any + Sync + Send {
 }
 
 #[async_trait]
-pub trait DynChannel<I: Id>: Any {
-    async fn send_dyn_message(&self, message: DynMessage) -> Result<(), Error>;
+pub trait DynChannel<I: Id>: Any + Sync + Send {
+    async fn send_dyn_message(&self, message: DynMessage) -> Result<DeliveryReceipt, Error>;
+
+    /// Serialize the message so that it can be persisted.
+    fn serialize_dyn_message(&self, message: &DynMessage) -> Result<serde_json::Value, Error>;
+
+    /// Deserialize a persisted message.
+    fn deserialize_dyn_message(&self, message: serde_json::Value) -> Result<DynMessage, Error>;
+
+    /// Deserialize the channel's contact, returning it with its `TypeId`.
+    fn deserialize_dyn_contact(
+        &self,
+        contact: serde_json::Value,
+    ) -> Result<(TypeId, Box<dyn Any + Send>), Error>;
 
     fn create_dyn_message(
         &self,
@@ -95,7 +128,7 @@ pub trait DynChannel<I: Id>: Any {
 
     fn render_dyn_template(
         &self,
-        notification_id: I,
+        notification_id: &I,
         context: &RenderContext,
         template_service: &TemplateService<I>,
     ) -> Result<DynMessageContents, Error>;
@@ -109,19 +142,48 @@ impl<I: Id, T: Channel<I>> DynChannel<I> for T {
         <Self as Channel<I>>::channel_type(self)
     }
 
-    async fn send_dyn_message(&self, message: DynMessage) -> Result<(), Error> {
+    async fn send_dyn_message(&self, message: DynMessage) -> Result<DeliveryReceipt, Error> {
         let message = message
             .take_message()
             .downcast::<T::Message>()
             .map_err(|inner| Error::Downcast {
                 context: Some("DynChannel to downcast contact to EmailAddress"),
-                found: (&*inner).type_id(),
+                found: (*inner).type_id(),
+                expected: TypeId::of::<T::Message>(),
+            })?;
+
+        <Self as Channel<I>>::send(self, *message).await
+    }
+
+    fn serialize_dyn_message(&self, message: &DynMessage) -> Result<serde_json::Value, Error> {
+        let message = message
+            .message()
+            .downcast_ref::<T::Message>()
+            .ok_or_else(|| Error::Downcast {
+                context: Some("DynMessage could not be downcasted to Self::Message"),
+                found: message.message().type_id(),
                 expected: TypeId::of::<T::Message>(),
             })?;
 
-        <Self as Channel<I>>::send(self, *message).await?;
+        let value =
+            serde_json::to_value(message as &dyn erased_serde::Serialize).map_err(Error::Serde)?;
 
-        Ok(())
+        Ok(value)
+    }
+
+    fn deserialize_dyn_message(&self, message: serde_json::Value) -> Result<DynMessage, Error> {
+        let message = serde_json::from_value::<T::Message>(message).map_err(Error::Serde)?;
+
+        Ok(DynMessage::new(message, self.channel_type()))
+    }
+
+    fn deserialize_dyn_contact(
+        &self,
+        contact: serde_json::Value,
+    ) -> Result<(TypeId, Box<dyn Any + Send>), Error> {
+        let contact = serde_json::from_value::<T::Contact>(contact).map_err(Error::Serde)?;
+
+        Ok((TypeId::of::<T::Contact>(), Box::new(contact)))
     }
 
     fn create_dyn_message(
@@ -134,7 +196,7 @@ impl<I: Id, T: Channel<I>> DynChannel<I> for T {
             .downcast::<T::Contact>()
             .map_err(|inner| Error::Downcast {
                 context: Some("DynChannel could not be downcasted to Self::Channel"),
-                found: (&*inner).type_id(),
+                found: (*inner).type_id(),
                 expected: TypeId::of::<T::Contact>(),
             })?;
 
@@ -143,7 +205,7 @@ impl<I: Id, T: Channel<I>> DynChannel<I> for T {
             .downcast::<T::RenderedTemplate>()
             .map_err(|inner| Error::Downcast {
                 context: Some("DynContents could not be downcasted to Self::Template::Output"),
-                found: (&*inner).type_id(),
+                found: (*inner).type_id(),
                 expected: TypeId::of::<T::RenderedTemplate>(),
             })?;
 
@@ -164,7 +226,7 @@ impl<I: Id, T: Channel<I>> DynChannel<I> for T {
             .downcast::<T::UserTemplate>()
             .map_err(|inner| Error::Downcast {
                 context: Some("DynContents could not be downcasted to Self::Template::Output"),
-                found: (&*inner).type_id(),
+                found: (*inner).type_id(),
                 expected: TypeId::of::<T::UserTemplate>(),
             })?;
 
@@ -175,7 +237,7 @@ impl<I: Id, T: Channel<I>> DynChannel<I> for T {
 
     fn render_dyn_template(
         &self,
-        notification_id: I,
+        notification_id: &I,
         context: &RenderContext,
         template_service: &TemplateService<I>,
     ) -> Result<DynMessageContents, Error> {
@@ -1,11 +1,12 @@
 use std::{
     any::{Any, TypeId},
     collections::HashMap,
+    sync::Arc,
 };
 
 use crate::{
     channel::{ChannelType, DynChannel},
-    Channel, Id,
+    Channel, Error, Id,
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -15,59 +16,192 @@ enum Key {
     Template(TypeId),
 }
 
-#[derive(Default)]
+/// The name of a channel instance, `None` for the channel's default instance.
+pub type Instance = Option<&'static str>;
+
+/// Picks the instance of each channel that a notification is sent with.
+pub type ChannelInstance = fn(ChannelType) -> Instance;
+
+/// Holds the registered channels. A channel type can have a default instance
+/// and any number of named instances, e.g. separate email channels for the
+/// transactional and marketing relays.
 pub struct ChannelRegistry<I: Id> {
-    channels: HashMap<ChannelType, Box<dyn DynChannel<I>>>,
+    channels: HashMap<(ChannelType, Instance), Arc<dyn DynChannel<I>>>,
+    /// The `TypeId` of the channel registered with each key.
+    kinds: HashMap<ChannelType, TypeId>,
     type_map: HashMap<Key, ChannelType>,
 }
 
+impl<I: Id> Default for ChannelRegistry<I> {
+    fn default() -> Self {
+        Self {
+            channels: HashMap::new(),
+            kinds: HashMap::new(),
+            type_map: HashMap::new(),
+        }
+    }
+}
+
 impl<I: Id> ChannelRegistry<I> {
-    pub fn register<C: Channel<I>>(&mut self, channel: C) {
+    /// Register the channel as its type's default instance.
+    pub fn register<C: Channel<I>>(&mut self, channel: C) -> Result<(), Error> {
+        self.register_instance(None, channel)
+    }
+
+    /// Register the channel as the named instance of its type. Fails if the
+    /// instance is already registered, or if a different channel uses the
+    /// same key, contact or template type.
+    pub fn register_instance<C: Channel<I>>(
+        &mut self,
+        instance: Instance,
+        channel: C,
+    ) -> Result<(), Error> {
         let channel_type = channel.channel_type();
-        let dyn_channel = channel.into_dyn();
 
-        self.channels.insert(channel_type, dyn_channel);
+        if self.channels.contains_key(&(channel_type, instance)) {
+            return Err(Error::DuplicateChannel {
+                key: channel_type.key(),
+                instance,
+            });
+        }
 
-        // types used for loop-ups
-        self.type_map
-            .insert(Key::Template(TypeId::of::<C::UserTemplate>()), channel_type);
-        self.type_map
-            .insert(Key::Message(TypeId::of::<C::Message>()), channel_type);
-        self.type_map
-            .insert(Key::Contact(TypeId::of::<C::Contact>()), channel_type);
+        self.replace_instance(instance, channel)
     }
 
-    pub fn find_by_template<T: Any>(&self) -> Option<&dyn DynChannel<I>> {
-        let key = Key::Template(TypeId::of::<T>());
+    /// Register the channel as the named instance of its type, replacing the
+    /// instance if it is already registered. Fails if a different channel
+    /// uses the same key, contact or template type.
+    pub fn replace_instance<C: Channel<I>>(
+        &mut self,
+        instance: Instance,
+        channel: C,
+    ) -> Result<(), Error> {
+        let channel_type = channel.channel_type();
+
+        if matches!(self.kinds.get(&channel_type), Some(type_id) if *type_id != TypeId::of::<C>()) {
+            return Err(Error::DuplicateChannel {
+                key: channel_type.key(),
+                instance,
+            });
+        }
+
+        // types used for loop-ups
+        let keys = [
+            Key::Template(TypeId::of::<C::UserTemplate>()),
+            Key::Message(TypeId::of::<C::Message>()),
+            Key::Contact(TypeId::of::<C::Contact>()),
+        ];
+
+        for key in &keys {
+            match self.type_map.get(key) {
+                Some(existing) if *existing != channel_type => {
+                    return Err(Error::ConflictingChannel {
+                        key: channel_type.key(),
+                        existing: existing.key(),
+                    })
+                }
+                _ => {}
+            }
+        }
 
-        if let Some(channel_type) = self.type_map.get(&key) {
-            return self.get(*channel_type);
+        let dyn_channel = channel.into_dyn();
+
+        self.channels
+            .insert((channel_type, instance), Arc::from(dyn_channel));
+        self.kinds.insert(channel_type, TypeId::of::<C>());
+
+        for key in keys {
+            self.type_map.insert(key, channel_type);
         }
 
-        None
+        Ok(())
     }
 
-    pub fn find_by_contact<T: Any>(&self) -> Option<&dyn DynChannel<I>> {
-        let key = Key::Contact(TypeId::of::<T>());
+    /// Remove the channel instance, returning false if it wasn't registered.
+    pub fn remove(&mut self, channel_type: ChannelType, instance: Instance) -> bool {
+        let removed = self.channels.remove(&(channel_type, instance)).is_some();
 
-        if let Some(channel_type) = self.type_map.get(&key) {
-            return self.get(*channel_type);
+        if !self.contains_type(channel_type) {
+            self.kinds.remove(&channel_type);
+            self.type_map.retain(|_, ty| *ty != channel_type);
         }
 
-        None
+        removed
+    }
+
+    /// Check if any instance of the channel type is registered.
+    pub fn contains_type(&self, channel_type: ChannelType) -> bool {
+        self.channels.keys().any(|(ty, _)| *ty == channel_type)
+    }
+
+    /// Find an instance of the channel for the template type: the default
+    /// instance, or else the first named instance by name. Templates are
+    /// shared by every instance of a channel type.
+    pub fn find_by_template<T: Any>(&self) -> Option<Arc<dyn DynChannel<I>>> {
+        let key = Key::Template(TypeId::of::<T>());
+
+        let channel_type = self.type_map.get(&key)?;
+
+        self.channels
+            .iter()
+            .filter(|((ty, _), _)| ty == channel_type)
+            .min_by_key(|((_, instance), _)| *instance)
+            .map(|(_, channel)| Arc::clone(channel))
     }
 
-    // pub fn get_channel_by_message<M: M()
+    pub fn find_by_contact<T: Any>(&self, instance: Instance) -> Option<Arc<dyn DynChannel<I>>> {
+        self.find_by_contact_type(TypeId::of::<T>(), instance)
+    }
+
+    /// Find the instance of the channel whose contact has the `TypeId`.
+    pub fn find_by_contact_type(
+        &self,
+        type_id: TypeId,
+        instance: Instance,
+    ) -> Option<Arc<dyn DynChannel<I>>> {
+        self.get(self.find_type_by_contact(type_id)?, instance)
+    }
+
+    /// Find the type of the channel whose contact has the `TypeId`, whichever
+    /// of its instances are registered.
+    pub fn find_type_by_contact(&self, type_id: TypeId) -> Option<ChannelType> {
+        self.type_map.get(&Key::Contact(type_id)).copied()
+    }
+
+    /// Find the channel instance by its channel's key.
+    pub fn find_by_key(&self, key: &str, instance: Option<&str>) -> Option<Arc<dyn DynChannel<I>>> {
+        self.channels
+            .iter()
+            .find(|((channel_type, ty_instance), _)| {
+                channel_type.key() == key && *ty_instance == instance
+            })
+            .map(|(_, channel)| Arc::clone(channel))
+    }
+
+    /// Find the type of the channel registered with the key, whichever of its
+    /// instances are registered.
+    pub fn find_type_by_key(&self, key: &str) -> Option<ChannelType> {
+        self.kinds
+            .keys()
+            .find(|channel_type| channel_type.key() == key)
+            .copied()
+    }
 
-    pub fn get(&self, channel_type: ChannelType) -> Option<&dyn DynChannel<I>> {
-        self.channels.get(&channel_type).map(|b| b.as_ref())
+    pub fn get(
+        &self,
+        channel_type: ChannelType,
+        instance: Instance,
+    ) -> Option<Arc<dyn DynChannel<I>>> {
+        self.channels.get(&(channel_type, instance)).cloned()
     }
 }
 
 #[cfg(test)]
 mod test_channel_registry {
+    use std::sync::Arc;
+
     use super::ChannelRegistry;
-    use crate::{test_utils::TestChannel, Channel};
+    use crate::{testing::TestChannel, Channel, Error};
 
     #[test]
     fn test_register_and_get_channel() {
@@ -76,10 +210,101 @@ mod test_channel_registry {
 
         let mut registry = ChannelRegistry::<u8>::default();
 
-        registry.register(channel);
+        registry.register(channel).unwrap();
 
-        let dyn_channel = registry.get(channel_type);
+        let dyn_channel = registry.get(channel_type, None);
 
         assert!(dyn_channel.is_some());
     }
+
+    #[test]
+    fn test_find_named_instance() {
+        let default = TestChannel::default();
+        let marketing = TestChannel::default();
+
+        let mut registry = ChannelRegistry::<u8>::default();
+
+        registry.register(default.clone()).unwrap();
+        registry
+            .register_instance(Some("marketing"), marketing.clone())
+            .unwrap();
+
+        let find = |instance| registry.find_by_contact::<crate::testing::TestContact>(instance);
+
+        assert!(!Arc::ptr_eq(
+            &find(None).unwrap(),
+            &find(Some("marketing")).unwrap()
+        ));
+
+        // a missing named instance doesn't fall back to the default instance
+        assert!(find(Some("unknown")).is_none());
+        assert!(registry.find_by_key("test", Some("unknown")).is_none());
+    }
+
+    #[test]
+    fn test_find_by_template_prefers_first_instance() {
+        let channel_type = <TestChannel as Channel<u8>>::channel_type(&TestChannel::default());
+
+        let mut registry = ChannelRegistry::<u8>::default();
+
+        for name in ["b", "a", "c"] {
+            registry
+                .register_instance(Some(name), TestChannel::default())
+                .unwrap();
+        }
+
+        let find = |registry: &ChannelRegistry<u8>| {
+            registry
+                .find_by_template::<crate::testing::TestTemplate>()
+                .unwrap()
+        };
+
+        assert!(Arc::ptr_eq(
+            &find(&registry),
+            &registry.get(channel_type, Some("a")).unwrap()
+        ));
+
+        registry.register(TestChannel::default()).unwrap();
+
+        assert!(Arc::ptr_eq(
+            &find(&registry),
+            &registry.get(channel_type, None).unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_remove_channel() {
+        let channel = TestChannel::default();
+        let channel_type = <TestChannel as Channel<u8>>::channel_type(&channel);
+
+        let mut registry = ChannelRegistry::<u8>::default();
+
+        registry.register(channel).unwrap();
+
+        assert!(registry.remove(channel_type, None));
+        assert!(registry.get(channel_type, None).is_none());
+        assert!(registry
+            .find_by_contact::<crate::testing::TestContact>(None)
+            .is_none());
+        assert!(!registry.remove(channel_type, None));
+    }
+
+    #[test]
+    fn test_rejects_duplicate_channel() {
+        let mut registry = ChannelRegistry::<u8>::default();
+
+        registry.register(TestChannel::default()).unwrap();
+
+        assert!(matches!(
+            registry.register(TestChannel::default()),
+            Err(Error::DuplicateChannel {
+                key: "test",
+                instance: None
+            })
+        ));
+
+        registry
+            .replace_instance(None, TestChannel::default())
+            .unwrap();
+    }
 }
@@ -1,8 +1,19 @@
-use std::any::Any;
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+};
 
-use serde::{de::DeserializeOwned, Serialize};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use crate::channel::ChannelType;
+use crate::{channel::ChannelType, quiet_hours::QuietHours, Priority};
+
+pub mod error;
+pub mod resolver;
+
+pub use error::Error;
+pub use resolver::{ContactResolver, InMemoryContactResolver};
 
 pub trait Contact: Any + Send + Serialize + DeserializeOwned {}
 
@@ -23,15 +34,138 @@ impl DynContact {
         }
     }
 
+    /// Wrap a contact that has already been boxed.
+    pub fn from_boxed(contact: Box<dyn Any + Send>, channel: ChannelType) -> Self {
+        Self { contact, channel }
+    }
+
     pub fn channel(&self) -> ChannelType {
         self.channel
     }
 
     pub fn contact(&self) -> &(dyn Any + Send) {
-        &self.contact
+        self.contact.as_ref()
     }
 
     pub fn take_contact(self) -> Box<dyn Any + Send> {
         self.contact
     }
 }
+
+/// A recipient that can be reached through one or more contacts. Each contact
+/// is expected to belong to a different channel, e.g. an email address and a
+/// phone number.
+#[derive(Default)]
+pub struct Recipient {
+    id: Option<String>,
+    timezone: Option<Tz>,
+    quiet_hours: Option<QuietHours>,
+    contacts: Vec<(TypeId, Box<dyn Any + Send>)>,
+}
+
+impl Recipient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the id of the application's user that the recipient represents.
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Get the id of the application's user that the recipient represents.
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Set the recipient's IANA timezone, which their quiet hours are in.
+    pub fn with_timezone(mut self, timezone: Tz) -> Self {
+        self.timezone = Some(timezone);
+        self
+    }
+
+    /// Get the recipient's timezone, defaults to UTC.
+    pub fn timezone(&self) -> Tz {
+        self.timezone.unwrap_or(Tz::UTC)
+    }
+
+    /// Set the hours during which non-critical notifications are deferred.
+    pub fn with_quiet_hours(mut self, quiet_hours: QuietHours) -> Self {
+        self.quiet_hours = Some(quiet_hours);
+        self
+    }
+
+    pub fn quiet_hours(&self) -> Option<QuietHours> {
+        self.quiet_hours
+    }
+
+    /// When the time is inside the recipient's quiet hours, get the time that
+    /// they end.
+    pub fn quiet_until(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.quiet_hours?.ends_after(now, self.timezone())
+    }
+
+    /// Add the contact to the recipient, replacing any existing contact of the
+    /// same type.
+    pub fn with_contact<C: Contact>(mut self, contact: C) -> Self {
+        self.add_contact(contact);
+        self
+    }
+
+    /// Add the contact to the recipient, replacing any existing contact of the
+    /// same type.
+    pub fn add_contact<C: Contact>(&mut self, contact: C) {
+        self.add_boxed_contact(TypeId::of::<C>(), Box::new(contact));
+    }
+
+    pub(crate) fn add_boxed_contact(&mut self, type_id: TypeId, contact: Box<dyn Any + Send>) {
+        match self.contacts.iter_mut().find(|(id, _)| *id == type_id) {
+            Some(entry) => entry.1 = contact,
+            None => self.contacts.push((type_id, contact)),
+        }
+    }
+
+    /// Get a reference to the recipient's contact of type `C`.
+    pub fn contact<C: Contact>(&self) -> Option<&C> {
+        self.contacts
+            .iter()
+            .find(|(id, _)| *id == TypeId::of::<C>())
+            .and_then(|(_, contact)| contact.downcast_ref::<C>())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.contacts.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.contacts.len()
+    }
+
+    /// Take the contacts along with the `TypeId` of each contact.
+    pub fn take_contacts(self) -> Vec<(TypeId, Box<dyn Any + Send>)> {
+        self.contacts
+    }
+}
+
+/// A recipient whose contacts are keyed by their channel's key rather than
+/// typed, e.g. when it's received as JSON from another service:
+/// `{ "id": "42", "contacts": { "email": { "email": "jane@example.com" } } }`.
+///
+/// Its quiet hours are given like
+/// `"timezone": "Europe/Paris", "quiet_hours": { "start": "22:00:00", "end": "07:00:00" }`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DynamicRecipient {
+    #[serde(default)]
+    pub id: Option<String>,
+    /// The IANA timezone that the quiet hours are in, defaults to UTC.
+    #[serde(default)]
+    pub timezone: Option<Tz>,
+    #[serde(default)]
+    pub quiet_hours: Option<QuietHours>,
+    /// The priority of the notification sent to the recipient, e.g.
+    /// `"critical"` to send it during their quiet hours.
+    #[serde(default)]
+    pub priority: Priority,
+    pub contacts: HashMap<String, serde_json::Value>,
+}
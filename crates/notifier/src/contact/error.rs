@@ -0,0 +1,21 @@
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("A contact does not exist with this id. (id = {0})")]
+    ContactNotFound(String),
+
+    #[error("A contact exists but is missing data for this field. (id = {0}, field = {1})")]
+    MissingField(String, &'static str),
+
+    #[error("The contact resolver failed")]
+    Resolver(#[source] anyhow::Error),
+}
+
+impl Error {
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, Error::ContactNotFound(_))
+    }
+
+    pub fn is_missing_field(&self) -> bool {
+        matches!(self, Error::MissingField(_, _))
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+
+use super::{Contact, Error, Recipient};
+
+/// Resolves an application's user id into a recipient that has a contact for
+/// each channel the user can be reached on.
+#[async_trait]
+pub trait ContactResolver: Sync + Send + 'static {
+    async fn resolve(&self, user_id: &str) -> Result<Recipient, Error>;
+}
+
+static_assertions::assert_obj_safe!(ContactResolver);
+
+type AddContact = Box<dyn Fn(&mut Recipient) + Sync + Send>;
+
+/// Resolver that keeps the users' contacts in memory, mostly useful for tests.
+#[derive(Default)]
+pub struct InMemoryContactResolver {
+    users: HashMap<String, Vec<AddContact>>,
+}
+
+impl InMemoryContactResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the contact to the user.
+    pub fn insert<C: Contact + Clone + Sync>(&mut self, user_id: impl Into<String>, contact: C) {
+        let add: AddContact = Box::new(move |recipient| recipient.add_contact(contact.clone()));
+        self.users.entry(user_id.into()).or_default().push(add);
+    }
+
+    /// Add the contact to the user.
+    pub fn with_contact<C: Contact + Clone + Sync>(
+        mut self,
+        user_id: impl Into<String>,
+        contact: C,
+    ) -> Self {
+        self.insert(user_id, contact);
+        self
+    }
+
+    /// Remove the user and all of their contacts.
+    pub fn remove(&mut self, user_id: &str) {
+        self.users.remove(user_id);
+    }
+}
+
+#[async_trait]
+impl ContactResolver for InMemoryContactResolver {
+    async fn resolve(&self, user_id: &str) -> Result<Recipient, Error> {
+        let contacts = self
+            .users
+            .get(user_id)
+            .ok_or_else(|| Error::ContactNotFound(user_id.to_owned()))?;
+
+        let mut recipient = Recipient::new().with_id(user_id);
+
+        for add in contacts {
+            add(&mut recipient);
+        }
+
+        Ok(recipient)
+    }
+}
+
+#[cfg(test)]
+mod test_in_memory_contact_resolver {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_resolves_user_contacts() {
+        let resolver = InMemoryContactResolver::new()
+            .with_contact("42", "user@test.com".to_string())
+            .with_contact("42", 4_200_u64);
+
+        let recipient = resolver.resolve("42").await.unwrap();
+
+        assert_eq!(recipient.id(), Some("42"));
+        assert_eq!(recipient.len(), 2);
+        assert_eq!(
+            recipient.contact::<String>().map(String::as_str),
+            Some("user@test.com")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unknown_user_is_not_found() {
+        let resolver = InMemoryContactResolver::new();
+
+        let result = resolver.resolve("42").await;
+
+        assert!(matches!(result, Err(e) if e.is_not_found()));
+    }
+}
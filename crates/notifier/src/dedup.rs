@@ -0,0 +1,231 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::{receipt::DeliveryReceipt, Error};
+
+pub mod memory;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+pub use memory::InMemoryDedupStore;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteDedupStore;
+
+/// How long a reservation blocks its key when the send that made it never
+/// finishes, e.g. because the process crashed, unless a store is given
+/// another lease.
+pub const DEFAULT_RESERVATION_LEASE: Duration = Duration::from_secs(60);
+
+/// The state of an idempotency key when a send reserves it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reservation {
+    /// The key was free and is now reserved for the send.
+    Reserved,
+    /// Another send reserved the key and hasn't finished.
+    InProgress,
+    /// A message was already sent with the key.
+    Sent(DeliveryReceipt),
+}
+
+/// Stores the receipts of the sends that had an idempotency key, so that a
+/// repeated send with the same key returns the original receipt instead of
+/// delivering the message again.
+///
+/// A send reserves its key before delivering the message, so concurrent sends
+/// with the same key don't both deliver. The stores forget sent keys after a
+/// time to live, and reserved keys after a shorter lease.
+#[async_trait]
+pub trait DedupStore: Sync + Send + 'static {
+    /// Get the receipt of the send with the key, if it hasn't expired.
+    async fn get(&self, key: &str) -> Result<Option<DeliveryReceipt>, Error>;
+
+    /// Reserve the key for a send, unless it is already reserved or sent.
+    /// Checking and reserving the key is atomic. The reservation expires
+    /// after the store's lease if the send doesn't finish.
+    async fn reserve(&self, key: &str) -> Result<Reservation, Error>;
+
+    /// Release the reservation of a send that failed, so that the key can be
+    /// used again. Does nothing if a receipt was stored for the key.
+    async fn release(&self, key: &str) -> Result<(), Error>;
+
+    /// Remember the receipt of the send with the key.
+    async fn insert(&self, key: &str, receipt: &DeliveryReceipt) -> Result<(), Error>;
+}
+
+static_assertions::assert_obj_safe!(DedupStore);
+
+#[cfg(test)]
+mod test_dedup {
+    use super::*;
+    use crate::testing::*;
+
+    #[tokio::test]
+    async fn test_send_with_idempotency_key() {
+        let channel = TestChannel::default();
+
+        let notifier = test_notifier(channel.clone())
+            .configure(|notifier| {
+                notifier.set_dedup_store(InMemoryDedupStore::new(Duration::from_secs(60)))
+            })
+            .build()
+            .unwrap();
+
+        let first = notifier
+            .send_message_to_contact_idempotent(
+                "key",
+                TestNotification::new(1, "first".to_string()),
+                TestContact("Destination (1)".to_string()),
+            )
+            .await
+            .unwrap();
+
+        let second = notifier
+            .send_message_to_contact_idempotent(
+                "key",
+                TestNotification::new(1, "second".to_string()),
+                TestContact("Destination (1)".to_string()),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(channel.messages.lock().unwrap().len(), 1);
+
+        // a send that reserved the key and hasn't finished blocks other sends
+        assert_eq!(
+            notifier
+                .dedup
+                .as_deref()
+                .unwrap()
+                .reserve("pending")
+                .await
+                .unwrap(),
+            Reservation::Reserved
+        );
+        assert!(matches!(
+            notifier
+                .send_message_to_contact_idempotent(
+                    "pending",
+                    TestNotification::new(1, "pending".to_string()),
+                    TestContact("Destination (1)".to_string()),
+                )
+                .await,
+            Err(Error::SendInProgress(_))
+        ));
+        assert_eq!(channel.messages.lock().unwrap().len(), 1);
+
+        notifier
+            .send_message_to_contact_idempotent(
+                "other",
+                TestNotification::new(2, "third".to_string()),
+                TestContact("Destination (2)".to_string()),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(channel.messages.lock().unwrap().len(), 2);
+    }
+
+    /// Dedup store that fails to store receipts.
+    struct TestUnwritableDedupStore(InMemoryDedupStore);
+
+    #[async_trait]
+    impl DedupStore for TestUnwritableDedupStore {
+        async fn get(&self, key: &str) -> Result<Option<DeliveryReceipt>, Error> {
+            self.0.get(key).await
+        }
+
+        async fn reserve(&self, key: &str) -> Result<Reservation, Error> {
+            self.0.reserve(key).await
+        }
+
+        async fn release(&self, key: &str) -> Result<(), Error> {
+            self.0.release(key).await
+        }
+
+        async fn insert(&self, _key: &str, _receipt: &DeliveryReceipt) -> Result<(), Error> {
+            Err(Error::Store(anyhow::Error::msg("the store is read only")))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_send_survives_failing_to_store_receipt() {
+        let channel = TestChannel::default();
+
+        let notifier = test_notifier(channel.clone())
+            .configure(|notifier| {
+                notifier.set_dedup_store(TestUnwritableDedupStore(InMemoryDedupStore::new(
+                    Duration::from_secs(60),
+                )))
+            })
+            .build()
+            .unwrap();
+
+        let send = |key| {
+            notifier.send_message_to_contact_idempotent(
+                key,
+                TestNotification::new(1, "first".to_string()),
+                TestContact("Destination (1)".to_string()),
+            )
+        };
+
+        assert!(send("key").await.is_ok());
+
+        // the key stays reserved, so a retry doesn't deliver it again
+        assert!(matches!(
+            send("key").await,
+            Err(Error::SendInProgress(key)) if key == "key"
+        ));
+        assert_eq!(channel.messages.lock().unwrap().len(), 1);
+
+        // a failed send releases its key
+        let send_failing = || {
+            notifier.send_message_to_contact_idempotent(
+                "failing",
+                TestNotification::new(1, "first".to_string()),
+                FailingContact("Destination (1)".to_string()),
+            )
+        };
+
+        assert!(matches!(send_failing().await, Err(Error::Provider(_))));
+        assert!(matches!(send_failing().await, Err(Error::Provider(_))));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_doesnt_store_idempotency_key() {
+        let channel = TestChannel::default();
+
+        let mut notifier = test_notifier(channel.clone())
+            .configure(|notifier| {
+                notifier.set_dedup_store(InMemoryDedupStore::new(Duration::from_secs(60)));
+                notifier.set_dry_run(true);
+            })
+            .build()
+            .unwrap();
+
+        let receipt = notifier
+            .send_message_to_contact_idempotent(
+                "key",
+                TestNotification::new(1, "first".to_string()),
+                TestContact("Destination (1)".to_string()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(receipt.provider_id(), "dry_run");
+        assert!(channel.messages.lock().unwrap().is_empty());
+
+        notifier.set_dry_run(false);
+
+        let receipt = notifier
+            .send_message_to_contact_idempotent(
+                "key",
+                TestNotification::new(1, "first".to_string()),
+                TestContact("Destination (1)".to_string()),
+            )
+            .await
+            .unwrap();
+        assert_ne!(receipt.provider_id(), "dry_run");
+        assert_eq!(channel.messages.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,164 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+
+use super::{DedupStore, Reservation, DEFAULT_RESERVATION_LEASE};
+use crate::{receipt::DeliveryReceipt, Error};
+
+/// Dedup store that keeps the receipts in memory for the time to live, they
+/// are lost when the process exits.
+pub struct InMemoryDedupStore {
+    ttl: Duration,
+    lease: Duration,
+    /// The expiry of each key, with its receipt once the send is done.
+    receipts: Mutex<HashMap<String, (Instant, Option<DeliveryReceipt>)>>,
+}
+
+impl InMemoryDedupStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            lease: DEFAULT_RESERVATION_LEASE,
+            receipts: Mutex::default(),
+        }
+    }
+
+    /// Set how long a reservation blocks its key when its send doesn't
+    /// finish.
+    pub fn with_lease(mut self, lease: Duration) -> Self {
+        self.lease = lease;
+        self
+    }
+
+    /// Remove the expired keys. Expired keys are also removed as new keys are
+    /// inserted.
+    pub fn purge(&self) {
+        let now = Instant::now();
+
+        self.receipts
+            .lock()
+            .unwrap()
+            .retain(|_, (expires_at, _)| *expires_at > now);
+    }
+
+    /// The number of keys that are remembered, including any that have
+    /// expired but haven't been purged.
+    pub fn len(&self) -> usize {
+        self.receipts.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl DedupStore for InMemoryDedupStore {
+    async fn get(&self, key: &str) -> Result<Option<DeliveryReceipt>, Error> {
+        let receipts = self.receipts.lock().unwrap();
+
+        Ok(receipts
+            .get(key)
+            .filter(|(expires_at, _)| *expires_at > Instant::now())
+            .and_then(|(_, receipt)| receipt.clone()))
+    }
+
+    async fn reserve(&self, key: &str) -> Result<Reservation, Error> {
+        self.purge();
+
+        let mut receipts = self.receipts.lock().unwrap();
+
+        let reservation = match receipts.get(key) {
+            Some((_, Some(receipt))) => Reservation::Sent(receipt.clone()),
+            Some((_, None)) => Reservation::InProgress,
+            None => {
+                receipts.insert(key.to_owned(), (Instant::now() + self.lease, None));
+                Reservation::Reserved
+            }
+        };
+
+        Ok(reservation)
+    }
+
+    async fn release(&self, key: &str) -> Result<(), Error> {
+        let mut receipts = self.receipts.lock().unwrap();
+
+        if let Some((_, None)) = receipts.get(key) {
+            receipts.remove(key);
+        }
+
+        Ok(())
+    }
+
+    async fn insert(&self, key: &str, receipt: &DeliveryReceipt) -> Result<(), Error> {
+        self.purge();
+
+        self.receipts.lock().unwrap().insert(
+            key.to_owned(),
+            (Instant::now() + self.ttl, Some(receipt.clone())),
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_in_memory_dedup_store {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_get_and_expire() {
+        let store = InMemoryDedupStore::new(Duration::from_millis(20));
+        let receipt = DeliveryReceipt::new("test").with_provider_message_id("1");
+
+        assert!(store.get("key").await.unwrap().is_none());
+
+        store.insert("key", &receipt).await.unwrap();
+        assert_eq!(store.get("key").await.unwrap(), Some(receipt));
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+
+        assert!(store.get("key").await.unwrap().is_none());
+
+        store.purge();
+        assert!(store.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reserve_and_release() {
+        let store = InMemoryDedupStore::new(Duration::from_secs(60));
+        let receipt = DeliveryReceipt::new("test").with_provider_message_id("1");
+
+        assert_eq!(store.reserve("key").await.unwrap(), Reservation::Reserved);
+        assert_eq!(store.reserve("key").await.unwrap(), Reservation::InProgress);
+        assert!(store.get("key").await.unwrap().is_none());
+
+        store.release("key").await.unwrap();
+        assert_eq!(store.reserve("key").await.unwrap(), Reservation::Reserved);
+
+        store.insert("key", &receipt).await.unwrap();
+        store.release("key").await.unwrap();
+
+        assert_eq!(
+            store.reserve("key").await.unwrap(),
+            Reservation::Sent(receipt)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reservation_lease_expires() {
+        let store =
+            InMemoryDedupStore::new(Duration::from_secs(60)).with_lease(Duration::from_millis(20));
+
+        assert_eq!(store.reserve("key").await.unwrap(), Reservation::Reserved);
+        assert_eq!(store.reserve("key").await.unwrap(), Reservation::InProgress);
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+
+        assert_eq!(store.reserve("key").await.unwrap(), Reservation::Reserved);
+    }
+}
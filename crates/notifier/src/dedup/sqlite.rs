@@ -0,0 +1,232 @@
+use std::{path::Path, sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::{DedupStore, Reservation, DEFAULT_RESERVATION_LEASE};
+use crate::{
+    receipt::DeliveryReceipt,
+    sqlite::{parse_json, store_error, to_millis},
+    Error,
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS notifier_dedup (
+    key TEXT PRIMARY KEY NOT NULL,
+    -- NULL while the key is reserved by a send that hasn't finished
+    receipt TEXT,
+    expires_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS notifier_dedup_expires ON notifier_dedup (expires_at);
+";
+
+/// Dedup store that persists the receipts in a SQLite database for the time
+/// to live. Queries run on the calling task, which is fine for SQLite's short
+/// lived writes.
+pub struct SqliteDedupStore {
+    ttl: chrono::Duration,
+    lease: Duration,
+    conn: Mutex<Connection>,
+}
+
+impl SqliteDedupStore {
+    /// Open the database at the path, creating the store's table if it doesn't
+    /// exist.
+    pub fn open<P: AsRef<Path>>(path: P, ttl: Duration) -> Result<Self, Error> {
+        let conn = Connection::open(path).map_err(store_error)?;
+        Self::from_connection(conn, ttl)
+    }
+
+    /// Open an in-memory database, mostly useful for tests.
+    pub fn open_in_memory(ttl: Duration) -> Result<Self, Error> {
+        let conn = Connection::open_in_memory().map_err(store_error)?;
+        Self::from_connection(conn, ttl)
+    }
+
+    /// Use the connection, creating the store's table if it doesn't exist.
+    pub fn from_connection(conn: Connection, ttl: Duration) -> Result<Self, Error> {
+        let ttl = chrono::Duration::from_std(ttl).map_err(|e| Error::Store(e.into()))?;
+
+        conn.execute_batch(SCHEMA).map_err(store_error)?;
+
+        Ok(Self {
+            ttl,
+            lease: DEFAULT_RESERVATION_LEASE,
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Set how long a reservation blocks its key when its send doesn't
+    /// finish.
+    pub fn with_lease(mut self, lease: Duration) -> Self {
+        self.lease = lease;
+        self
+    }
+
+    /// Remove the expired keys. Expired keys are also removed as new keys are
+    /// inserted.
+    pub fn purge(&self) -> Result<(), Error> {
+        self.conn
+            .lock()
+            .unwrap()
+            .execute(
+                "DELETE FROM notifier_dedup WHERE expires_at <= ?1",
+                params![to_millis(Utc::now())],
+            )
+            .map_err(store_error)?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl DedupStore for SqliteDedupStore {
+    async fn get(&self, key: &str) -> Result<Option<DeliveryReceipt>, Error> {
+        self.conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT receipt FROM notifier_dedup
+                WHERE key = ?1 AND expires_at > ?2 AND receipt IS NOT NULL",
+                params![key, to_millis(Utc::now())],
+                |row| {
+                    let receipt: String = row.get(0)?;
+                    parse_json(0, &receipt)
+                },
+            )
+            .optional()
+            .map_err(store_error)
+    }
+
+    async fn reserve(&self, key: &str) -> Result<Reservation, Error> {
+        self.purge()?;
+
+        let lease = chrono::Duration::from_std(self.lease).map_err(|e| Error::Store(e.into()))?;
+        let now = Utc::now();
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction().map_err(store_error)?;
+
+        let reserved = tx
+            .execute(
+                "INSERT OR IGNORE INTO notifier_dedup (key, receipt, expires_at)
+                VALUES (?1, NULL, ?2)",
+                params![key, to_millis(now + lease)],
+            )
+            .map_err(store_error)?;
+
+        let reservation = if reserved > 0 {
+            Reservation::Reserved
+        } else {
+            let receipt: Option<String> = tx
+                .query_row(
+                    "SELECT receipt FROM notifier_dedup WHERE key = ?1",
+                    params![key],
+                    |row| row.get(0),
+                )
+                .map_err(store_error)?;
+
+            match receipt {
+                Some(receipt) => {
+                    Reservation::Sent(serde_json::from_str(&receipt).map_err(Error::Serde)?)
+                }
+                None => Reservation::InProgress,
+            }
+        };
+
+        tx.commit().map_err(store_error)?;
+
+        Ok(reservation)
+    }
+
+    async fn release(&self, key: &str) -> Result<(), Error> {
+        self.conn
+            .lock()
+            .unwrap()
+            .execute(
+                "DELETE FROM notifier_dedup WHERE key = ?1 AND receipt IS NULL",
+                params![key],
+            )
+            .map_err(store_error)?;
+
+        Ok(())
+    }
+
+    async fn insert(&self, key: &str, receipt: &DeliveryReceipt) -> Result<(), Error> {
+        self.purge()?;
+
+        let receipt = serde_json::to_string(receipt).map_err(Error::Serde)?;
+
+        self.conn
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT OR REPLACE INTO notifier_dedup (key, receipt, expires_at)
+                VALUES (?1, ?2, ?3)",
+                params![key, receipt, to_millis(Utc::now() + self.ttl)],
+            )
+            .map_err(store_error)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_sqlite_dedup_store {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_get_and_expire() {
+        let store = SqliteDedupStore::open_in_memory(Duration::from_millis(20)).unwrap();
+        let receipt = DeliveryReceipt::new("test").with_provider_message_id("1");
+
+        assert!(store.get("key").await.unwrap().is_none());
+
+        store.insert("key", &receipt).await.unwrap();
+
+        let found = store.get("key").await.unwrap().unwrap();
+        assert_eq!(found.provider_id(), "test");
+        assert_eq!(found.provider_message_id(), Some("1"));
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+
+        assert!(store.get("key").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_reserve_and_release() {
+        let store = SqliteDedupStore::open_in_memory(Duration::from_secs(60)).unwrap();
+        let receipt = DeliveryReceipt::new("test").with_provider_message_id("1");
+
+        assert_eq!(store.reserve("key").await.unwrap(), Reservation::Reserved);
+        assert_eq!(store.reserve("key").await.unwrap(), Reservation::InProgress);
+        assert!(store.get("key").await.unwrap().is_none());
+
+        store.release("key").await.unwrap();
+        assert_eq!(store.reserve("key").await.unwrap(), Reservation::Reserved);
+
+        store.insert("key", &receipt).await.unwrap();
+        store.release("key").await.unwrap();
+
+        assert_eq!(
+            store.reserve("key").await.unwrap(),
+            Reservation::Sent(receipt)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reservation_lease_expires() {
+        let store = SqliteDedupStore::open_in_memory(Duration::from_secs(60))
+            .unwrap()
+            .with_lease(Duration::from_millis(20));
+
+        assert_eq!(store.reserve("key").await.unwrap(), Reservation::Reserved);
+        assert_eq!(store.reserve("key").await.unwrap(), Reservation::InProgress);
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+
+        assert_eq!(store.reserve("key").await.unwrap(), Reservation::Reserved);
+    }
+}
@@ -0,0 +1,222 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{report::Report, Error, Id, Priority};
+
+pub mod memory;
+pub mod worker;
+
+pub use memory::InMemoryDigestStore;
+pub use worker::{DigestWorker, DigestWorkerOptions};
+
+/// Buffers the notifications with the id for each recipient, and sends them
+/// as a single notification with the digest's id once the interval has passed
+/// since the first notification was buffered.
+///
+/// The digest's template is rendered with `notifications`, the list of the
+/// buffered notifications' data, and `count`, the length of the list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Digest<I: Id> {
+    pub notification_id: I,
+    pub digest_id: I,
+    pub interval: Duration,
+    /// The priority that the digest is sent with, normal by default.
+    pub priority: Priority,
+}
+
+impl<I: Id> Digest<I> {
+    pub fn new(notification_id: I, digest_id: I, interval: Duration) -> Self {
+        Self {
+            notification_id,
+            digest_id,
+            interval,
+            priority: Priority::Normal,
+        }
+    }
+
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+/// A notification waiting to be sent in a digest.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DigestEntry {
+    /// The notification serialized as JSON.
+    pub data: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The notifications buffered for a recipient.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DigestBatch {
+    pub recipient_id: String,
+    pub notification_id: String,
+    /// The entries in the order they were added.
+    pub entries: Vec<DigestEntry>,
+}
+
+/// Storage for the notifications that are buffered for the digests.
+#[async_trait]
+pub trait DigestStore: Sync + Send + 'static {
+    /// Buffer the notification for the recipient.
+    async fn push(
+        &self,
+        recipient_id: &str,
+        notification_id: &str,
+        entry: DigestEntry,
+    ) -> Result<(), Error>;
+
+    /// Return the recipients' buffered notifications with the id, when the
+    /// first of them was created at or before the time. They stay buffered
+    /// until they're removed once the digest is sent.
+    async fn due(
+        &self,
+        notification_id: &str,
+        created_before: DateTime<Utc>,
+    ) -> Result<Vec<DigestBatch>, Error>;
+
+    /// Remove the first `count` notifications buffered for the recipient,
+    /// keeping those that were buffered after the digest was taken.
+    async fn remove(
+        &self,
+        recipient_id: &str,
+        notification_id: &str,
+        count: usize,
+    ) -> Result<(), Error>;
+}
+
+static_assertions::assert_obj_safe!(DigestStore);
+
+/// The outcome of sending a recipient's digest.
+#[derive(Debug)]
+pub struct DigestOutcome {
+    pub recipient_id: String,
+    pub notification_id: String,
+    /// The number of notifications that were in the digest.
+    pub count: usize,
+    pub report: Result<Report, Error>,
+}
+
+#[cfg(test)]
+mod test_digest {
+    use super::*;
+    use crate::{contact::InMemoryContactResolver, testing::*, Notification, Notifier};
+
+    #[derive(serde::Serialize)]
+    struct TestDigestNotification;
+
+    impl Notification for TestDigestNotification {
+        type Id = &'static str;
+
+        fn id() -> Self::Id {
+            "test_digest"
+        }
+    }
+
+    /// Set the digest of the test notifications and the stores it needs.
+    fn set_digest<C: crate::Contact + Clone + Sync>(
+        notifier: &mut Notifier<&'static str>,
+        contact: C,
+    ) {
+        notifier.set_contact_resolver(InMemoryContactResolver::new().with_contact("42", contact));
+        notifier.set_digest_store(InMemoryDigestStore::new());
+        notifier.set_digest(Digest::new(
+            TestNotification::id(),
+            TestDigestNotification::id(),
+            Duration::ZERO,
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_flush_digests() {
+        let channel = TestChannel::default();
+
+        let notifier = test_notifier(channel.clone())
+            .template::<TestDigestNotification, TestTemplate>(TestTemplate(
+                "{{count}}:{% for n in notifications %} {{n.message}}{% endfor %}",
+            ))
+            .configure(|notifier| set_digest(notifier, TestContact("Destination (42)".to_string())))
+            .build()
+            .unwrap();
+
+        for message in ["first", "second"] {
+            notifier
+                .add_to_digest("42", TestNotification::new(1, message.to_string()))
+                .await
+                .unwrap();
+        }
+
+        let outcomes = notifier.flush_digests().await.unwrap();
+
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].recipient_id, "42");
+        assert_eq!(outcomes[0].count, 2);
+        assert!(outcomes[0].report.as_ref().unwrap().is_success());
+
+        {
+            let messages = channel.messages.lock().unwrap();
+            assert_eq!(messages.len(), 1);
+            assert_eq!(messages[0].contents.output, "2: first second");
+            assert_eq!(messages[0].contents.notification_id, "test_digest");
+        }
+
+        assert!(notifier.flush_digests().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_flush_digests_keeps_failed_digest() {
+        let notifier = test_notifier(TestChannel::default())
+            .template::<TestDigestNotification, FailingTemplate>(FailingTemplate("{{count}}"))
+            .configure(|notifier| {
+                set_digest(notifier, FailingContact("Destination (42)".to_string()))
+            })
+            .build()
+            .unwrap();
+
+        notifier
+            .add_to_digest("42", TestNotification::new(1, "first".to_string()))
+            .await
+            .unwrap();
+
+        let outcomes = notifier.flush_digests().await.unwrap();
+
+        assert_eq!(outcomes.len(), 1);
+        assert!(outcomes[0]
+            .report
+            .as_ref()
+            .unwrap()
+            .failures()
+            .next()
+            .is_some());
+
+        // the digest is retried along with the notifications buffered since
+        notifier
+            .add_to_digest("42", TestNotification::new(2, "second".to_string()))
+            .await
+            .unwrap();
+
+        let outcomes = notifier.flush_digests().await.unwrap();
+
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_add_to_digest_requires_digest() {
+        let notifier = test_notifier(TestChannel::default())
+            .configure(|notifier| notifier.set_digest_store(InMemoryDigestStore::new()))
+            .build()
+            .unwrap();
+
+        let result = notifier
+            .add_to_digest("42", TestNotification::new(1, "first".to_string()))
+            .await;
+
+        assert!(matches!(result, Err(Error::UnknownDigest(_))));
+    }
+}
@@ -0,0 +1,94 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use super::{DigestBatch, DigestEntry, DigestStore};
+use crate::Error;
+
+/// Digest store that keeps the buffered notifications in memory, they are
+/// lost when the process exits.
+#[derive(Default)]
+pub struct InMemoryDigestStore {
+    entries: Mutex<HashMap<(String, String), Vec<DigestEntry>>>,
+}
+
+impl InMemoryDigestStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of notifications buffered for the recipient.
+    pub fn len(&self, recipient_id: &str, notification_id: &str) -> usize {
+        self.entries
+            .lock()
+            .unwrap()
+            .get(&(recipient_id.to_owned(), notification_id.to_owned()))
+            .map_or(0, Vec::len)
+    }
+}
+
+#[async_trait]
+impl DigestStore for InMemoryDigestStore {
+    async fn push(
+        &self,
+        recipient_id: &str,
+        notification_id: &str,
+        entry: DigestEntry,
+    ) -> Result<(), Error> {
+        self.entries
+            .lock()
+            .unwrap()
+            .entry((recipient_id.to_owned(), notification_id.to_owned()))
+            .or_default()
+            .push(entry);
+
+        Ok(())
+    }
+
+    async fn due(
+        &self,
+        notification_id: &str,
+        created_before: DateTime<Utc>,
+    ) -> Result<Vec<DigestBatch>, Error> {
+        let entries = self.entries.lock().unwrap();
+
+        let batches = entries
+            .iter()
+            .filter(|((_, id), buffered)| {
+                id == notification_id
+                    && buffered
+                        .first()
+                        .is_some_and(|entry| entry.created_at <= created_before)
+            })
+            .map(|((recipient_id, notification_id), buffered)| DigestBatch {
+                recipient_id: recipient_id.clone(),
+                notification_id: notification_id.clone(),
+                entries: buffered.clone(),
+            })
+            .collect();
+
+        Ok(batches)
+    }
+
+    async fn remove(
+        &self,
+        recipient_id: &str,
+        notification_id: &str,
+        count: usize,
+    ) -> Result<(), Error> {
+        let mut entries = self.entries.lock().unwrap();
+
+        let key = (recipient_id.to_owned(), notification_id.to_owned());
+
+        if let Some(buffered) = entries.get_mut(&key) {
+            buffered.drain(..count.min(buffered.len()));
+
+            if buffered.is_empty() {
+                entries.remove(&key);
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use std::{future::Future, time::Duration};
+
+use super::DigestOutcome;
+use crate::{Error, Id, Notifier};
+
+#[derive(Debug, Clone)]
+pub struct DigestWorkerOptions {
+    /// How often the store is checked for digests that are due.
+    pub poll_interval: Duration,
+}
+
+impl Default for DigestWorkerOptions {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Periodically sends the notifier's digests that are due.
+pub struct DigestWorker<'a, I: Id> {
+    notifier: &'a Notifier<I>,
+    options: DigestWorkerOptions,
+}
+
+impl<'a, I: Id> DigestWorker<'a, I> {
+    pub fn new(notifier: &'a Notifier<I>, options: DigestWorkerOptions) -> Self {
+        Self { notifier, options }
+    }
+
+    /// Send the digests that are due.
+    pub async fn run_once(&self) -> Result<Vec<DigestOutcome>, Error> {
+        self.notifier.flush_digests().await
+    }
+
+    /// Keep sending the digests as they become due until the shutdown future
+    /// completes. Returns early if the store fails.
+    pub async fn run_until<F: Future<Output = ()>>(&self, shutdown: F) -> Result<(), Error> {
+        tokio::pin!(shutdown);
+
+        loop {
+            for outcome in self.run_once().await? {
+                if let Err(e) = &outcome.report {
+                    tracing::warn!(
+                        recipient_id = %outcome.recipient_id,
+                        notification_id = %outcome.notification_id,
+                        error = %e,
+                        "failed to send digest"
+                    );
+                }
+            }
+
+            tokio::select! {
+                biased;
+                _ = &mut shutdown => return Ok(()),
+                _ = tokio::time::sleep(self.options.poll_interval) => {}
+            }
+        }
+    }
+}
@@ -1,22 +1,65 @@
+pub mod builder;
 pub mod channel;
 pub mod contact;
+pub mod dedup;
+pub mod digest;
 pub mod message;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod middleware;
 pub mod notification;
+pub mod outbox;
+pub mod preference;
 pub mod provider;
+pub mod quiet_hours;
+pub mod receipt;
+mod redact;
+pub mod report;
+pub mod schedule;
+#[cfg(feature = "sqlite")]
+mod sqlite;
 pub mod template;
 
-use std::any::{Any, TypeId};
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, RwLock},
+    time::Instant,
+};
 
-use channel::registry::ChannelRegistry;
+pub use builder::NotifierBuilder;
 pub use channel::Channel;
+use channel::{
+    registry::{ChannelInstance, ChannelRegistry, Instance},
+    ChannelType, DynChannel,
+};
+use chrono::{DateTime, Utc};
 use contact::{Contact, DynContact};
-pub use notification::{Id, Notification};
+pub use contact::{ContactResolver, DynamicRecipient, Error as ContactError, Recipient};
+use dedup::{DedupStore, Reservation};
+use digest::{Digest, DigestEntry, DigestOutcome, DigestStore};
+use message::DynMessage;
+use middleware::HookContext;
+pub use middleware::Middleware;
+pub use notification::{EmbeddedTemplate, Id, Notification, Priority};
+#[cfg(feature = "derive")]
+pub use notifier_derive::Notification;
+use outbox::{OutboxMessage, OutboxStore};
+use preference::Decision;
+pub use preference::PreferenceStore;
 pub use provider::{Error as ProviderError, Provider};
+pub use quiet_hours::QuietHours;
+pub use receipt::DeliveryReceipt;
+pub use report::Report;
+use report::{FailureReason, Outcome, SkipReason};
+use schedule::{ScheduleHandle, ScheduleStore, ScheduledJob};
 pub use template::TemplateError;
 use template::{engine::RenderContext, TemplateService};
+use tracing::{field, Instrument};
 
-#[cfg(test)]
-pub(crate) mod test_utils;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -26,9 +69,72 @@ pub enum Error {
     #[error("ProviderError: {0:?}")]
     Provider(#[from] ProviderError),
 
+    #[error("ContactError: {0:?}")]
+    Contact(#[from] ContactError),
+
+    #[error("A contact resolver has not been set on the notifier")]
+    NoContactResolver,
+
+    #[error("The preference store failed")]
+    Preference(#[source] anyhow::Error),
+
+    #[error("An outbox has not been set on the notifier")]
+    NoOutbox,
+
+    #[error("A schedule store has not been set on the notifier")]
+    NoScheduleStore,
+
+    #[error("A dedup store has not been set on the notifier")]
+    NoDedupStore,
+
+    #[error("Another send with the idempotency key hasn't finished: {0}")]
+    SendInProgress(String),
+
+    #[error("A digest store has not been set on the notifier")]
+    NoDigestStore,
+
+    #[error("A digest has not been set for this notification: {0}")]
+    UnknownDigest(String),
+
+    #[error("A notification with this id has not been registered: {0}")]
+    UnknownNotification(String),
+
+    #[error("The store failed")]
+    Store(#[source] anyhow::Error),
+
+    #[error("A middleware blocked the message: {0}")]
+    Blocked(String),
+
+    #[error("Failed to serialize or deserialize the message")]
+    Serde(#[source] serde_json::Error),
+
     #[error("Channel could not be found: {0}")]
     UnknownChannel(&'static str),
 
+    #[error("A channel with this key has not been registered: {0}")]
+    UnknownChannelKey(String),
+
+    #[error(
+        "The channel {key} has no {} instance",
+        .instance.as_deref().unwrap_or("default")
+    )]
+    UnknownChannelInstance {
+        key: String,
+        instance: Option<String>,
+    },
+
+    #[error("A channel is already registered with this key: {key}")]
+    DuplicateChannel {
+        key: &'static str,
+        instance: Option<&'static str>,
+    },
+
+    #[error("The channel {key} uses the same contact or template type as the channel {existing}")]
+    ConflictingChannel {
+        key: &'static str,
+        existing: &'static str,
+    },
+
     #[error("Failed to downcast")]
     Downcast {
         found: TypeId,
@@ -37,134 +143,2041 @@ pub enum Error {
     },
 }
 
-#[derive(Default)]
+impl Error {
+    /// Whether the error is transient, meaning that sending the message again
+    /// may succeed.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::Provider(e) => e.is_transient(),
+            _ => false,
+        }
+    }
+}
+
 pub struct Notifier<I: Id> {
-    channels: ChannelRegistry<I>,
-    templates: TemplateService<I>,
+    channels: RwLock<ChannelRegistry<I>>,
+    templates: RwLock<TemplateService<I>>,
+    contact_resolver: Option<Box<dyn ContactResolver>>,
+    preferences: Option<Box<dyn PreferenceStore<I>>>,
+    outbox: Option<Box<dyn OutboxStore>>,
+    schedules: Option<Arc<dyn ScheduleStore>>,
+    dedup: Option<Box<dyn DedupStore>>,
+    digests: HashMap<I, Digest<I>>,
+    digest_store: Option<Box<dyn DigestStore>>,
+    middleware: Vec<Box<dyn Middleware<I>>>,
+    reveal_recipients: bool,
+    dry_run: bool,
+    #[cfg(feature = "metrics")]
+    metrics: Option<metrics::Metrics>,
 }
 
-impl<I: Id + Default> Notifier<I> {
+impl<I: Id> Default for Notifier<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I: Id> Notifier<I> {
     pub fn new() -> Self {
-        Self::default()
+        Self {
+            channels: RwLock::default(),
+            templates: RwLock::default(),
+            contact_resolver: None,
+            preferences: None,
+            outbox: None,
+            schedules: None,
+            dedup: None,
+            digests: HashMap::new(),
+            digest_store: None,
+            middleware: Vec::new(),
+            reveal_recipients: false,
+            dry_run: false,
+            #[cfg(feature = "metrics")]
+            metrics: None,
+        }
+    }
+
+    /// Build a notifier whose registrations are validated at startup.
+    pub fn builder() -> NotifierBuilder<I> {
+        NotifierBuilder::new()
     }
 }
 
 impl<I: Id> Notifier<I> {
-    /// Add the channel to the notifier's registry.
-    pub fn register_channel<C: Channel<I>>(&mut self, channel: C) {
-        self.channels.register(channel)
+    /// Add the channel to the notifier's registry. Fails if a channel is
+    /// already registered with the same key, or if another channel uses the
+    /// same contact or template type.
+    pub fn register_channel<C: Channel<I>>(&self, channel: C) -> Result<(), Error> {
+        self.channels.write().unwrap().register(channel)
+    }
+
+    /// Add the channel as a named instance of its type. Notifications choose
+    /// the instance with [`Notification::channel_instance`], and share the
+    /// templates registered for the channel type.
+    pub fn register_channel_instance<C: Channel<I>>(
+        &self,
+        name: &'static str,
+        channel: C,
+    ) -> Result<(), Error> {
+        self.channels
+            .write()
+            .unwrap()
+            .register_instance(Some(name), channel)
+    }
+
+    /// Replace the channel's default instance, e.g. to rotate a provider's
+    /// credentials, keeping the templates registered for the channel.
+    pub fn replace_channel<C: Channel<I>>(&self, channel: C) -> Result<(), Error> {
+        self.channels
+            .write()
+            .unwrap()
+            .replace_instance(None, channel)
+    }
+
+    /// Replace the channel's named instance.
+    pub fn replace_channel_instance<C: Channel<I>>(
+        &self,
+        name: &'static str,
+        channel: C,
+    ) -> Result<(), Error> {
+        self.channels
+            .write()
+            .unwrap()
+            .replace_instance(Some(name), channel)
+    }
+
+    /// Remove the channel's default instance. Returns false if the channel
+    /// wasn't registered.
+    pub fn remove_channel(&self, channel_type: ChannelType) -> bool {
+        self.remove_instance(channel_type, None)
+    }
+
+    /// Remove the channel's named instance. Returns false if the instance
+    /// wasn't registered.
+    pub fn remove_channel_instance(&self, channel_type: ChannelType, name: &'static str) -> bool {
+        self.remove_instance(channel_type, Some(name))
+    }
+
+    /// Remove the instance, along with the channel type's templates when it
+    /// was the last instance.
+    fn remove_instance(&self, channel_type: ChannelType, instance: Instance) -> bool {
+        let mut channels = self.channels.write().unwrap();
+
+        let removed = channels.remove(channel_type, instance);
+
+        if !channels.contains_type(channel_type) {
+            self.templates.write().unwrap().remove_channel(channel_type);
+        }
+
+        removed
+    }
+
+    /// Set the resolver used to find the contacts of an application's user.
+    pub fn set_contact_resolver<R: ContactResolver>(&mut self, resolver: R) {
+        self.contact_resolver = Some(Box::new(resolver));
+    }
+
+    /// Set the store consulted for the recipients' channel preferences.
+    pub fn set_preference_store<P: PreferenceStore<I>>(&mut self, preferences: P) {
+        self.preferences = Some(Box::new(preferences));
+    }
+
+    /// Set the outbox that messages are enqueued into.
+    pub fn set_outbox<S: OutboxStore>(&mut self, outbox: S) {
+        self.outbox = Some(Box::new(outbox));
+    }
+
+    pub fn outbox(&self) -> Option<&dyn OutboxStore> {
+        self.outbox.as_deref()
+    }
+
+    /// Set the store that scheduled notifications are persisted in, and that
+    /// notifications deferred by the recipients' quiet hours are kept in.
+    /// Without it, quiet hours are ignored.
+    pub fn set_schedule_store<S: ScheduleStore>(&mut self, store: S) {
+        self.schedules = Some(Arc::new(store));
+    }
+
+    pub fn schedule_store(&self) -> Option<&dyn ScheduleStore> {
+        self.schedules.as_deref()
+    }
+
+    /// Set the store that remembers the receipts of sends with an idempotency
+    /// key.
+    pub fn set_dedup_store<S: DedupStore>(&mut self, store: S) {
+        self.dedup = Some(Box::new(store));
+    }
+
+    /// Set the store that buffers the notifications for the digests.
+    pub fn set_digest_store<S: DigestStore>(&mut self, store: S) {
+        self.digest_store = Some(Box::new(store));
+    }
+
+    /// Buffer the digest's notifications, replacing any digest previously set
+    /// for the notification. The digest's id needs a template registered for
+    /// the channels it's sent on.
+    pub fn set_digest(&mut self, digest: Digest<I>) {
+        self.digests.insert(digest.notification_id.clone(), digest);
+    }
+
+    /// Add the middleware, whose hooks run around rendering and sending every
+    /// message.
+    pub fn add_middleware<M: Middleware<I>>(&mut self, middleware: M) {
+        self.middleware.push(Box::new(middleware));
+    }
+
+    /// Record the recipients' full addresses in the tracing spans, they are
+    /// redacted by default.
+    pub fn set_reveal_recipients(&mut self, reveal: bool) {
+        self.reveal_recipients = reveal;
+    }
+
+    /// Run the whole pipeline without handing the messages to the channels'
+    /// providers. Sends return a receipt from the `dry_run` provider with the
+    /// serialized message in its `message` metadata. Messages replayed from
+    /// the outbox or schedule aren't sent either, and idempotent sends don't
+    /// record their keys.
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// Record the sends in the metrics.
+    #[cfg(feature = "metrics")]
+    pub fn set_metrics(&mut self, metrics: metrics::Metrics) {
+        self.metrics = Some(metrics);
     }
 
-    /// Register a template for the notification.
+    /// Register a template for the notification, replacing any template
+    /// previously registered for it on the template's channel.
     pub fn register_notification<N: Notification<Id = I>, T: Any>(
-        &mut self,
+        &self,
         template: T,
     ) -> Result<(), Error> {
         let channel = self
             .channels
+            .read()
+            .unwrap()
             .find_by_template::<T>()
             .ok_or(Error::UnknownChannel(
                 "A channel for this template type has not yet been registered.",
             ))?;
 
-        channel.register_dyn_template(N::id(), Box::new(template), &mut self.templates)?;
+        channel.register_dyn_template(
+            N::id(),
+            Box::new(template),
+            &mut self.templates.write().unwrap(),
+        )?;
 
         Ok(())
     }
 
+    /// Remove the notification's template for the channel. Returns false if a
+    /// template wasn't registered.
+    pub fn remove_template<N: Notification<Id = I>>(&self, channel_type: ChannelType) -> bool {
+        self.templates
+            .write()
+            .unwrap()
+            .remove_template(&N::id(), channel_type)
+    }
+
+    /// Remove the notification's templates for every channel. Returns false if
+    /// no templates were registered.
+    pub fn remove_notification<N: Notification<Id = I>>(&self) -> bool {
+        self.templates
+            .write()
+            .unwrap()
+            .remove_notification(&N::id())
+    }
+
     /// Send the message to a specific channel's contact.
     pub async fn send_message_to_contact<N: Notification<Id = I>, C: Contact>(
         &self,
         notification: N,
         contact: C,
-    ) -> Result<(), Error> {
-        let channel = self
-            .channels
-            .find_by_contact::<C>()
-            .ok_or(Error::UnknownChannel(
-                "A channel for this contact type has not yet been registered.",
-            ))?;
+    ) -> Result<DeliveryReceipt, Error> {
+        let channel =
+            self.find_channel_by_contact(TypeId::of::<C>(), &N::id(), N::channel_instance)?;
+
+        let span = tracing::info_span!(
+            "send_message_to_contact",
+            notification_id = %N::id(),
+            channel_type = channel.get_channel_type().key(),
+            recipient = redact::contact(&contact, self.reveal_recipients),
+            outcome = field::Empty,
+            latency_ms = field::Empty,
+        );
+
+        let start = Instant::now();
+
+        let result = async {
+            let context = RenderContext::with_data(&notification)?;
+
+            let dyn_contact = DynContact::new(contact, channel.get_channel_type());
+
+            self.send_with_channel(channel.as_ref(), &N::id(), &context, dyn_contact)
+                .await
+        }
+        .instrument(span.clone())
+        .await;
+
+        span.record("latency_ms", start.elapsed().as_millis() as u64);
+        span.record("outcome", if result.is_ok() { "sent" } else { "failed" });
+
+        result
+    }
+
+    /// Render the message that would be sent to a specific channel's contact,
+    /// without sending it. The message is serialized as JSON. Render hooks
+    /// run but send hooks don't.
+    pub async fn preview<N: Notification<Id = I>, C: Contact>(
+        &self,
+        notification: N,
+        contact: C,
+    ) -> Result<serde_json::Value, Error> {
+        let channel =
+            self.find_channel_by_contact(TypeId::of::<C>(), &N::id(), N::channel_instance)?;
+
+        let context = RenderContext::with_data(&notification)?;
+
+        let dyn_contact = DynContact::new(contact, channel.get_channel_type());
+
+        let dyn_message = self
+            .create_message(channel.as_ref(), &N::id(), &context, dyn_contact)
+            .await?;
+
+        channel.serialize_dyn_message(&dyn_message)
+    }
+
+    /// Send the message to a specific channel's contact unless a message was
+    /// already sent with the idempotency key, in which case the original
+    /// receipt is returned. Fails with [`Error::SendInProgress`] while another
+    /// send with the key hasn't finished.
+    pub async fn send_message_to_contact_idempotent<N: Notification<Id = I>, C: Contact>(
+        &self,
+        idempotency_key: &str,
+        notification: N,
+        contact: C,
+    ) -> Result<DeliveryReceipt, Error> {
+        let dedup = self.dedup.as_deref().ok_or(Error::NoDedupStore)?;
+
+        // a dry run isn't recorded, so the key can still be sent for real
+        if self.dry_run {
+            return match dedup.get(idempotency_key).await? {
+                Some(receipt) => Ok(receipt),
+                None => self.send_message_to_contact(notification, contact).await,
+            };
+        }
+
+        match dedup.reserve(idempotency_key).await? {
+            Reservation::Reserved => {}
+            Reservation::InProgress => {
+                return Err(Error::SendInProgress(idempotency_key.to_owned()))
+            }
+            Reservation::Sent(receipt) => return Ok(receipt),
+        }
+
+        let receipt = match self.send_message_to_contact(notification, contact).await {
+            Ok(receipt) => receipt,
+            Err(e) => {
+                if let Err(release_error) = dedup.release(idempotency_key).await {
+                    tracing::warn!(
+                        idempotency_key,
+                        error = %release_error,
+                        "failed to release the idempotency key"
+                    );
+                }
+                return Err(e);
+            }
+        };
+
+        // the message was sent, so failing here would make the caller send it
+        // again. The key stays reserved, which still blocks a second send.
+        if let Err(e) = dedup.insert(idempotency_key, &receipt).await {
+            tracing::warn!(
+                idempotency_key,
+                error = %e,
+                "failed to store the receipt of the idempotent send"
+            );
+        }
+
+        Ok(receipt)
+    }
+
+    /// Render the message for a specific channel's contact and add it to the
+    /// outbox, to be sent later by an [`outbox::OutboxWorker`].
+    pub async fn enqueue_message_to_contact<N: Notification<Id = I>, C: Contact>(
+        &self,
+        notification: N,
+        contact: C,
+    ) -> Result<uuid::Uuid, Error> {
+        let outbox = self.outbox().ok_or(Error::NoOutbox)?;
+
+        let channel =
+            self.find_channel_by_contact(TypeId::of::<C>(), &N::id(), N::channel_instance)?;
 
         let notification_id = N::id();
         let context = RenderContext::with_data(&notification)?;
 
-        let dyn_contents =
-            channel.render_dyn_template(notification_id, &context, &self.templates)?;
+        let dyn_contact = DynContact::new(contact, channel.get_channel_type());
+
+        let dyn_message = self
+            .create_message(channel.as_ref(), &notification_id, &context, dyn_contact)
+            .await?;
+
+        let message = OutboxMessage::new(
+            channel.get_channel_type().key(),
+            notification_id.to_string(),
+            channel.serialize_dyn_message(&dyn_message)?,
+        )
+        .with_instance(N::channel_instance(channel.get_channel_type()));
+        let id = message.id;
+
+        outbox.enqueue(message).await?;
+
+        Ok(id)
+    }
+
+    /// Send a message from the outbox with the channel it was rendered for.
+    pub async fn send_outbox_message(
+        &self,
+        message: &OutboxMessage,
+    ) -> Result<DeliveryReceipt, Error> {
+        self.send_serialized_message(
+            &message.channel,
+            message.instance.as_deref(),
+            &message.notification_id,
+            message.payload.clone(),
+        )
+        .await
+    }
+
+    /// Render the message for a specific channel's contact now and persist it
+    /// in the schedule store, to be sent at the given time by a
+    /// [`schedule::ScheduleWorker`].
+    pub async fn schedule<N: Notification<Id = I>, C: Contact>(
+        &self,
+        notification: N,
+        contact: C,
+        at: DateTime<Utc>,
+    ) -> Result<ScheduleHandle, Error> {
+        let channel =
+            self.find_channel_by_contact(TypeId::of::<C>(), &N::id(), N::channel_instance)?;
+
+        let context = RenderContext::with_data(&notification)?;
 
         let dyn_contact = DynContact::new(contact, channel.get_channel_type());
 
-        let dyn_message = channel.create_dyn_message(dyn_contact, dyn_contents)?;
+        self.defer_with_channel(
+            channel.as_ref(),
+            N::channel_instance(channel.get_channel_type()),
+            &N::id(),
+            &context,
+            dyn_contact,
+            at,
+        )
+        .await
+    }
 
-        channel.send_dyn_message(dyn_message).await?;
+    /// Cancel the scheduled job. Returns false when the job has already been
+    /// sent or cancelled.
+    pub async fn cancel_scheduled(&self, id: uuid::Uuid) -> Result<bool, Error> {
+        let store = self.schedule_store().ok_or(Error::NoScheduleStore)?;
 
-        Ok(())
+        store.cancel(id).await
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::{test_utils::*, *};
+    /// Send a persisted message with the channel that has the name. Fails
+    /// with [`Error::UnknownNotification`] when its notification is no longer
+    /// registered.
+    pub(crate) async fn send_serialized_message(
+        &self,
+        channel: &str,
+        instance: Option<&str>,
+        notification_id: &str,
+        payload: serde_json::Value,
+    ) -> Result<DeliveryReceipt, Error> {
+        let channel = self.find_channel_by_key(channel, instance, notification_id)?;
 
-    #[test]
-    fn test_register_notification() {
-        let mut notifier = Notifier::<&'static str>::default();
+        let dyn_message = channel.deserialize_dyn_message(payload)?;
 
-        let channel = TestChannel::default();
+        // the hooks need the typed id, so a message whose notification was
+        // removed isn't sent, whether or not there are hooks to run
+        let hook = HookContext {
+            channel_type: channel.get_channel_type(),
+            notification_id: self.find_notification_id(notification_id)?,
+        };
 
-        notifier.register_channel(channel);
+        self.send_message(channel.as_ref(), &hook, dyn_message)
+            .await
+    }
 
-        let template = TestTemplate("message = {{message}}");
+    /// Send the notification on every channel that the recipient has a contact
+    /// for and that has a template registered for the notification.
+    ///
+    /// Failing to send on one channel doesn't prevent sending on the others,
+    /// the outcome for each channel is recorded in the returned report.
+    /// Contacts that don't belong to a registered channel are ignored.
+    ///
+    /// When the recipient has an id and a preference store is set, the store
+    /// decides whether each channel is used, skipped or redirected. A
+    /// notification redirected to a channel that the recipient has no contact
+    /// for is sent to the contact found by the contact resolver, if one is set.
+    pub async fn notify<N: Notification<Id = I>>(
+        &self,
+        recipient: Recipient,
+        notification: N,
+    ) -> Result<Report, Error> {
+        let context = RenderContext::with_data(&notification)?;
 
-        let result = notifier.register_notification::<TestNotification, TestTemplate>(template);
+        self.notify_with_context(
+            recipient,
+            &N::id(),
+            N::channel_instance,
+            notification.priority(),
+            &context,
+        )
+        .await
+    }
 
-        assert!(result.is_ok());
+    async fn notify_with_context(
+        &self,
+        recipient: Recipient,
+        notification_id: &I,
+        instance_of: ChannelInstance,
+        priority: Priority,
+        context: &RenderContext,
+    ) -> Result<Report, Error> {
+        let recipient_id = recipient.id().map(ToOwned::to_owned);
+
+        // without a schedule store the notification can't be deferred, so
+        // it's sent during the quiet hours
+        let quiet_until = match priority {
+            Priority::Critical => None,
+            _ if self.schedules.is_none() => None,
+            _ => recipient.quiet_until(Utc::now()),
+        };
+
+        let contacts = recipient.take_contacts();
+
+        let channels = {
+            let channels = self.channels.read().unwrap();
+
+            contacts
+                .iter()
+                .filter(|(type_id, _)| channels.find_type_by_contact(*type_id).is_some())
+                .count()
+        };
+
+        let span = tracing::info_span!(
+            "notify",
+            notification_id = %notification_id,
+            channels,
+        );
+
+        self.notify_contacts(
+            recipient_id.as_deref(),
+            contacts,
+            notification_id,
+            instance_of,
+            quiet_until,
+            context,
+        )
+        .instrument(span)
+        .await
     }
 
-    #[tokio::test]
-    async fn test_send_notification() {
-        let mut notifier = Notifier::<&'static str>::default();
+    /// Send the notification to each of the recipient's contacts, as decided
+    /// by the preference store.
+    async fn notify_contacts(
+        &self,
+        recipient_id: Option<&str>,
+        contacts: Vec<(TypeId, Box<dyn Any + Send>)>,
+        notification_id: &I,
+        instance_of: ChannelInstance,
+        quiet_until: Option<DateTime<Utc>>,
+        context: &RenderContext,
+    ) -> Result<Report, Error> {
+        let mut report = Report::new();
+        let mut allowed = Vec::new();
+        let mut redirects = Vec::new();
 
-        let channel = TestChannel::default();
-        let template = TestTemplate("message = {{message}}");
+        for (type_id, contact) in contacts {
+            let (channel_type, channel) = {
+                let channels = self.channels.read().unwrap();
 
-        notifier.register_channel(channel.clone());
+                match channels.find_type_by_contact(type_id) {
+                    Some(channel_type) => (
+                        channel_type,
+                        channels.get(channel_type, instance_of(channel_type)),
+                    ),
+                    None => continue,
+                }
+            };
 
-        notifier
-            .register_notification::<TestNotification, TestTemplate>(template)
-            .unwrap();
+            // a notification pinned to an instance isn't sent with another one
+            let Some(channel) = channel else {
+                let instance = instance_of(channel_type);
+                self.observe_failure(
+                    &notification_id.to_string(),
+                    channel_type.key(),
+                    FailureReason::ChannelResolution,
+                );
+                report.push(
+                    channel_type,
+                    Outcome::Failed(Error::UnknownChannelInstance {
+                        key: channel_type.key().to_owned(),
+                        instance: instance.map(ToOwned::to_owned),
+                    }),
+                );
+                continue;
+            };
 
-        let notification = TestNotification::new(1, "first notification".to_string());
-        let contact = TestContact("Destination (1)".to_string());
+            let decision = match (&self.preferences, recipient_id) {
+                (Some(preferences), Some(recipient_id)) => {
+                    preferences
+                        .decide(recipient_id, notification_id, channel_type)
+                        .await
+                }
+                _ => Ok(Decision::Allow),
+            };
 
-        let result = notifier
-            .send_message_to_contact(notification, contact)
+            match decision {
+                Ok(Decision::Allow) => allowed.push((channel, contact)),
+                Ok(Decision::Skip) => {
+                    report.push(channel_type, Outcome::Skipped(SkipReason::OptedOut))
+                }
+                Ok(Decision::Redirect(target)) => {
+                    report.push(channel_type, Outcome::Redirected(target));
+                    redirects.push(target);
+                }
+                Err(e) => report.push(channel_type, Outcome::Failed(e)),
+            }
+        }
+
+        // a redirect is satisfied by the recipient's contact for the target
+        // channel, otherwise the target's contact is resolved for the recipient
+        let mut resolved = None;
+
+        for target in redirects {
+            let is_allowed = allowed
+                .iter()
+                .any(|(channel, _)| channel.get_channel_type() == target);
+
+            if is_allowed || report.contains(target) {
+                continue;
+            }
+
+            match self
+                .find_redirect_contact(recipient_id, target, instance_of(target), &mut resolved)
+                .await
+            {
+                Ok(Some(entry)) => allowed.push(entry),
+                Ok(None) => report.push(target, Outcome::Skipped(SkipReason::MissingContact)),
+                Err(e) => {
+                    if matches!(e, Error::UnknownChannelInstance { .. }) {
+                        self.observe_failure(
+                            &notification_id.to_string(),
+                            target.key(),
+                            FailureReason::ChannelResolution,
+                        );
+                    }
+                    report.push(target, Outcome::Failed(e))
+                }
+            }
+        }
+
+        for (channel, contact) in allowed {
+            let channel_type = channel.get_channel_type();
+
+            if !self
+                .templates
+                .read()
+                .unwrap()
+                .has_template(notification_id, channel_type)
+            {
+                report.push(channel_type, Outcome::Skipped(SkipReason::TemplateNotFound));
+                continue;
+            }
+
+            let dyn_contact = DynContact::from_boxed(contact, channel_type);
+
+            let span = tracing::info_span!(
+                "notify_channel",
+                channel_type = channel_type.key(),
+                outcome = field::Empty,
+            );
+
+            let outcome = async {
+                match quiet_until {
+                    Some(until) => match self
+                        .defer_with_channel(
+                            channel.as_ref(),
+                            instance_of(channel_type),
+                            notification_id,
+                            context,
+                            dyn_contact,
+                            until,
+                        )
+                        .await
+                    {
+                        Ok(handle) => Outcome::Deferred(handle),
+                        Err(e) => Outcome::Failed(e),
+                    },
+                    None => match self
+                        .send_with_channel(channel.as_ref(), notification_id, context, dyn_contact)
+                        .await
+                    {
+                        Ok(receipt) => Outcome::Sent(receipt),
+                        Err(e) => Outcome::Failed(e),
+                    },
+                }
+            }
+            .instrument(span.clone())
             .await;
 
-        assert!(result.is_ok());
+            span.record("outcome", outcome.as_str());
 
-        let messages = channel.messages.lock().unwrap();
-        let len = messages.len();
-        assert_eq!(len, 1);
+            report.push(channel_type, outcome);
+        }
+
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            for outcome in report.outcomes() {
+                if let Outcome::Skipped(reason) = outcome.outcome {
+                    metrics.record_skip(&notification_id.to_string(), outcome.channel_type, reason);
+                }
+            }
+        }
+
+        Ok(report)
     }
 
-    #[tokio::test]
-    async fn test_fails_to_register_notification_on_unknown_channel() {
-        let mut notifier = Notifier::<&'static str>::default();
-        let template = TestTemplate("message = {{message}}");
+    /// Resolve the user's contacts with the notifier's contact resolver and
+    /// notify them on every channel they can be reached on.
+    pub async fn notify_user<N: Notification<Id = I>>(
+        &self,
+        user_id: &str,
+        notification: N,
+    ) -> Result<Report, Error> {
+        let resolver = self
+            .contact_resolver
+            .as_ref()
+            .ok_or(Error::NoContactResolver)?;
 
-        let result = notifier.register_notification::<TestNotification, TestTemplate>(template);
+        let recipient = resolver.resolve(user_id).await?;
 
-        assert!(result.is_err());
-        assert!(matches!(result, Err(Error::UnknownChannel(_))));
+        self.notify(recipient, notification).await
     }
 
-    #[tokio::test]
-    async fn test_fails_to_send_unknown_notification() {
-        let mut notifier = Notifier::<&'static str>::default();
+    /// Send the notification with the id to a contact of the channel with the
+    /// key, with the notification's data and the contact given as JSON. This
+    /// lets a gateway send notifications without linking their types.
+    pub async fn send_dynamic(
+        &self,
+        notification_id: &str,
+        data: &serde_json::Value,
+        channel: &str,
+        contact: serde_json::Value,
+    ) -> Result<DeliveryReceipt, Error> {
+        let notification_id = self.find_notification_id(notification_id)?;
+
+        let channel = self.find_channel_by_key(channel, None, &notification_id.to_string())?;
+
+        let (_, contact) = channel.deserialize_dyn_contact(contact)?;
+        let dyn_contact = DynContact::from_boxed(contact, channel.get_channel_type());
+
+        let context = RenderContext::with_data(data)?;
+
+        self.send_with_channel(channel.as_ref(), &notification_id, &context, dyn_contact)
+            .await
+    }
+
+    /// Send the notification with the id to the recipient's contacts, with
+    /// the notification's data given as JSON. Fails without sending if a
+    /// contact's channel isn't registered or the contact is malformed.
+    ///
+    /// The notification is sent with the recipient's priority, and is
+    /// deferred during their quiet hours like with [`Notifier::notify`].
+    pub async fn notify_dynamic(
+        &self,
+        notification_id: &str,
+        data: &serde_json::Value,
+        recipient: DynamicRecipient,
+    ) -> Result<Report, Error> {
+        let notification_id = self.find_notification_id(notification_id)?;
+
+        let mut typed = Recipient::new();
+
+        if let Some(id) = recipient.id {
+            typed = typed.with_id(id);
+        }
+        if let Some(timezone) = recipient.timezone {
+            typed = typed.with_timezone(timezone);
+        }
+        if let Some(quiet_hours) = recipient.quiet_hours {
+            typed = typed.with_quiet_hours(quiet_hours);
+        }
+
+        for (key, contact) in recipient.contacts {
+            let channel = self.find_channel_by_key(&key, None, &notification_id.to_string())?;
+
+            let (type_id, contact) = channel.deserialize_dyn_contact(contact)?;
+            typed.add_boxed_contact(type_id, contact);
+        }
+
+        let context = RenderContext::with_data(data)?;
+
+        self.notify_with_context(
+            typed,
+            &notification_id,
+            |_| None,
+            recipient.priority,
+            &context,
+        )
+        .await
+    }
+
+    /// Buffer the notification for the user, to be sent in the digest that
+    /// has been set for the notification's id.
+    pub async fn add_to_digest<N: Notification<Id = I>>(
+        &self,
+        user_id: &str,
+        notification: N,
+    ) -> Result<(), Error> {
+        let store = self.digest_store.as_deref().ok_or(Error::NoDigestStore)?;
+
+        let notification_id = N::id();
+
+        if !self.digests.contains_key(&notification_id) {
+            return Err(Error::UnknownDigest(notification_id.to_string()));
+        }
+
+        let entry = DigestEntry {
+            data: serde_json::to_value(&notification).map_err(Error::Serde)?,
+            created_at: Utc::now(),
+        };
+
+        store
+            .push(user_id, &notification_id.to_string(), entry)
+            .await
+    }
+
+    /// Send the digests that are due to the users, resolving their contacts
+    /// with the notifier's contact resolver.
+    ///
+    /// The buffered notifications are removed from the store once the digest
+    /// is sent on a channel, or when no channel failed. Otherwise they stay
+    /// buffered and the digest is retried on the next flush.
+    pub async fn flush_digests(&self) -> Result<Vec<DigestOutcome>, Error> {
+        let store = self.digest_store.as_deref().ok_or(Error::NoDigestStore)?;
+
+        let mut outcomes = Vec::new();
+
+        for digest in self.digests.values() {
+            let interval =
+                chrono::Duration::from_std(digest.interval).map_err(|e| Error::Store(e.into()))?;
+
+            let batches = store
+                .due(&digest.notification_id.to_string(), Utc::now() - interval)
+                .await?;
+
+            for batch in batches {
+                let count = batch.entries.len();
+                let notifications = batch
+                    .entries
+                    .into_iter()
+                    .map(|entry| entry.data)
+                    .collect::<Vec<_>>();
+
+                let report = self
+                    .notify_user_with_data(
+                        &batch.recipient_id,
+                        &digest.digest_id,
+                        digest.priority,
+                        &serde_json::json!({
+                            "notifications": notifications,
+                            "count": count,
+                        }),
+                    )
+                    .await;
+
+                let delivered = report.as_ref().is_ok_and(|report| {
+                    report
+                        .outcomes()
+                        .iter()
+                        .any(|o| o.outcome.is_sent() || o.outcome.is_deferred())
+                        || report.failures().next().is_none()
+                });
+
+                if delivered {
+                    store
+                        .remove(&batch.recipient_id, &batch.notification_id, count)
+                        .await?;
+                }
+
+                outcomes.push(DigestOutcome {
+                    recipient_id: batch.recipient_id,
+                    notification_id: batch.notification_id,
+                    count,
+                    report,
+                });
+            }
+        }
+
+        Ok(outcomes)
+    }
+
+    async fn notify_user_with_data(
+        &self,
+        user_id: &str,
+        notification_id: &I,
+        priority: Priority,
+        data: &serde_json::Value,
+    ) -> Result<Report, Error> {
+        let resolver = self
+            .contact_resolver
+            .as_ref()
+            .ok_or(Error::NoContactResolver)?;
+
+        let recipient = resolver.resolve(user_id).await?;
+        let context = RenderContext::with_data(data)?;
+
+        self.notify_with_context(recipient, notification_id, |_| None, priority, &context)
+            .await
+    }
+
+    /// Find the registered notification id from its string form.
+    fn find_notification_id(&self, notification_id: &str) -> Result<I, Error> {
+        self.templates
+            .read()
+            .unwrap()
+            .find_notification_id(notification_id)
+            .ok_or_else(|| Error::UnknownNotification(notification_id.to_owned()))
+    }
+
+    /// Find the instance that the notification uses of the channel for the
+    /// contact type. Fails if the channel or the named instance isn't
+    /// registered.
+    fn find_channel_by_contact(
+        &self,
+        type_id: TypeId,
+        notification_id: &I,
+        instance_of: ChannelInstance,
+    ) -> Result<Arc<dyn DynChannel<I>>, Error> {
+        let channels = self.channels.read().unwrap();
+
+        let Some(channel_type) = channels.find_type_by_contact(type_id) else {
+            self.observe_failure(
+                &notification_id.to_string(),
+                "unknown",
+                FailureReason::ChannelResolution,
+            );
+
+            return Err(Error::UnknownChannel(
+                "A channel for this contact type has not yet been registered.",
+            ));
+        };
+
+        let instance = instance_of(channel_type);
+
+        channels.get(channel_type, instance).ok_or_else(|| {
+            self.observe_failure(
+                &notification_id.to_string(),
+                channel_type.key(),
+                FailureReason::ChannelResolution,
+            );
+
+            Error::UnknownChannelInstance {
+                key: channel_type.key().to_owned(),
+                instance: instance.map(ToOwned::to_owned),
+            }
+        })
+    }
+
+    /// Find the instance of the channel with the key. Fails if the channel or
+    /// the named instance isn't registered.
+    fn find_channel_by_key(
+        &self,
+        key: &str,
+        instance: Option<&str>,
+        notification_id: &str,
+    ) -> Result<Arc<dyn DynChannel<I>>, Error> {
+        let channels = self.channels.read().unwrap();
+
+        let result = if channels.find_type_by_key(key).is_none() {
+            Err(Error::UnknownChannelKey(key.to_owned()))
+        } else {
+            channels
+                .find_by_key(key, instance)
+                .ok_or_else(|| Error::UnknownChannelInstance {
+                    key: key.to_owned(),
+                    instance: instance.map(ToOwned::to_owned),
+                })
+        };
+
+        if result.is_err() {
+            self.observe_failure(notification_id, key, FailureReason::ChannelResolution);
+        }
+
+        result
+    }
+
+    /// Find the recipient's contact for the channel that a notification was
+    /// redirected to, resolving the recipient's contacts with the contact
+    /// resolver the first time. Returns `None` when it can't be found.
+    async fn find_redirect_contact(
+        &self,
+        recipient_id: Option<&str>,
+        target: ChannelType,
+        instance: Instance,
+        resolved: &mut Option<Vec<(TypeId, Box<dyn Any + Send>)>>,
+    ) -> Result<Option<(Arc<dyn DynChannel<I>>, Box<dyn Any + Send>)>, Error> {
+        let (Some(resolver), Some(recipient_id)) = (&self.contact_resolver, recipient_id) else {
+            return Ok(None);
+        };
+
+        if resolved.is_none() {
+            *resolved = Some(resolver.resolve(recipient_id).await?.take_contacts());
+        }
+        let contacts = resolved.get_or_insert_with(Vec::new);
+
+        let channels = self.channels.read().unwrap();
+
+        let Some(index) = contacts
+            .iter()
+            .position(|(type_id, _)| channels.find_type_by_contact(*type_id) == Some(target))
+        else {
+            return Ok(None);
+        };
+
+        let channel =
+            channels
+                .get(target, instance)
+                .ok_or_else(|| Error::UnknownChannelInstance {
+                    key: target.key().to_owned(),
+                    instance: instance.map(ToOwned::to_owned),
+                })?;
+
+        let (_, contact) = contacts.swap_remove(index);
+
+        Ok(Some((channel, contact)))
+    }
+
+    /// Render the notification's template for the channel and send it to the
+    /// contact.
+    async fn send_with_channel(
+        &self,
+        channel: &dyn DynChannel<I>,
+        notification_id: &I,
+        context: &RenderContext,
+        contact: DynContact,
+    ) -> Result<DeliveryReceipt, Error> {
+        let dyn_message = self
+            .create_message(channel, notification_id, context, contact)
+            .await?;
+
+        let hook = HookContext {
+            channel_type: channel.get_channel_type(),
+            notification_id: notification_id.clone(),
+        };
+
+        self.send_message(channel, &hook, dyn_message).await
+    }
+
+    /// Send the message with the channel, running the middleware's hooks
+    /// around it.
+    async fn send_message(
+        &self,
+        channel: &dyn DynChannel<I>,
+        hook: &HookContext<I>,
+        mut message: DynMessage,
+    ) -> Result<DeliveryReceipt, Error> {
+        let notification_id = hook.notification_id.to_string();
+
+        let result = async {
+            for middleware in &self.middleware {
+                if let Err(e) = middleware.before_send(hook, &mut message).await {
+                    self.observe_failure(
+                        &notification_id,
+                        hook.channel_type.key(),
+                        FailureReason::Middleware,
+                    );
+                    return Err(e);
+                }
+            }
+
+            let receipt = self.deliver(channel, &notification_id, message).await?;
+
+            for middleware in self.middleware.iter().rev() {
+                middleware.after_send(hook, &receipt).await;
+            }
+
+            Ok(receipt)
+        }
+        .await;
+
+        if let Err(e) = &result {
+            self.on_error(hook, e).await;
+        }
+
+        result
+    }
+
+    /// Hand the message to the channel's provider. In dry-run mode it isn't
+    /// sent, the receipt holds the serialized message instead.
+    async fn deliver(
+        &self,
+        channel: &dyn DynChannel<I>,
+        notification_id: &str,
+        message: DynMessage,
+    ) -> Result<DeliveryReceipt, Error> {
+        if self.dry_run {
+            return Ok(DeliveryReceipt::new("dry_run")
+                .with_metadata("message", channel.serialize_dyn_message(&message)?));
+        }
+
+        self.observe_send(
+            notification_id,
+            channel.get_channel_type(),
+            channel.send_dyn_message(message),
+        )
+        .await
+    }
+
+    /// Render the notification's template for the channel and persist it in
+    /// the schedule store to be sent to the contact at the time.
+    async fn defer_with_channel(
+        &self,
+        channel: &dyn DynChannel<I>,
+        instance: Instance,
+        notification_id: &I,
+        context: &RenderContext,
+        contact: DynContact,
+        until: DateTime<Utc>,
+    ) -> Result<ScheduleHandle, Error> {
+        let store = self.schedules.clone().ok_or(Error::NoScheduleStore)?;
+
+        let dyn_message = self
+            .create_message(channel, notification_id, context, contact)
+            .await?;
+
+        let job = ScheduledJob::new(
+            channel.get_channel_type().key(),
+            notification_id.to_string(),
+            channel.serialize_dyn_message(&dyn_message)?,
+            until,
+        )
+        .with_instance(instance);
+        let id = job.id;
+
+        store.insert(job).await?;
+
+        Ok(ScheduleHandle::new(id, until, store))
+    }
+
+    /// Render the notification's template for the channel and create the
+    /// message for the contact, running the middleware's hooks around it.
+    async fn create_message(
+        &self,
+        channel: &dyn DynChannel<I>,
+        notification_id: &I,
+        context: &RenderContext,
+        contact: DynContact,
+    ) -> Result<DynMessage, Error> {
+        let hook = HookContext {
+            channel_type: channel.get_channel_type(),
+            notification_id: notification_id.clone(),
+        };
+
+        let span = tracing::debug_span!(
+            "render_message",
+            notification_id = %notification_id,
+            channel_type = hook.channel_type.key(),
+            outcome = field::Empty,
+        );
+
+        let result = async {
+            for middleware in &self.middleware {
+                middleware
+                    .before_render(&hook)
+                    .await
+                    .map_err(|e| (FailureReason::Middleware, e))?;
+            }
+
+            let dyn_contents = channel
+                .render_dyn_template(notification_id, context, &self.templates.read().unwrap())
+                .map_err(|e| (FailureReason::of_render(&e), e))?;
+
+            let mut message = channel
+                .create_dyn_message(contact, dyn_contents)
+                .map_err(|e| (FailureReason::Render, e))?;
+
+            for middleware in self.middleware.iter().rev() {
+                middleware
+                    .after_render(&hook, &mut message)
+                    .await
+                    .map_err(|e| (FailureReason::Middleware, e))?;
+            }
+
+            Ok(message)
+        }
+        .instrument(span.clone())
+        .await
+        .map_err(|(reason, e)| {
+            self.observe_failure(
+                &notification_id.to_string(),
+                hook.channel_type.key(),
+                reason,
+            );
+            e
+        });
+
+        span.record(
+            "outcome",
+            if result.is_ok() { "rendered" } else { "failed" },
+        );
+
+        if let Err(e) = &result {
+            self.on_error(&hook, e).await;
+        }
+
+        result
+    }
+
+    /// Record the send in the metrics, when they are enabled.
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    async fn observe_send<F: Future<Output = Result<DeliveryReceipt, Error>>>(
+        &self,
+        notification_id: &str,
+        channel_type: ChannelType,
+        send: F,
+    ) -> Result<DeliveryReceipt, Error> {
+        let start = Instant::now();
+        let result = send.await;
+
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.record_send(notification_id, channel_type, start.elapsed(), &result);
+        }
+
+        result
+    }
+
+    /// Record a failure that happened before the provider's send in the
+    /// metrics, when they are enabled.
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    fn observe_failure(&self, notification_id: &str, channel: &str, reason: FailureReason) {
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.record_failure(notification_id, channel, reason);
+        }
+    }
+
+    async fn on_error(&self, hook: &HookContext<I>, error: &Error) {
+        for middleware in self.middleware.iter().rev() {
+            middleware.on_error(hook, error).await;
+        }
+    }
+}
+
+static_assertions::assert_impl_all!(Notifier<&'static str>: Send, Sync);
+
+#[cfg(test)]
+mod test {
+    use super::{testing::*, *};
+
+    #[test]
+    fn test_register_notification() {
+        let notifier = Notifier::<&'static str>::default();
+
+        let channel = TestChannel::default();
+
+        notifier.register_channel(channel).unwrap();
+
+        let template = TestTemplate("message = {{message}}");
+
+        let result = notifier.register_notification::<TestNotification, TestTemplate>(template);
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_send_notification() {
+        let channel = TestChannel::default();
+
+        let notifier = test_notifier(channel.clone()).build().unwrap();
+
+        let notification = TestNotification::new(1, "first notification".to_string());
+        let contact = TestContact("Destination (1)".to_string());
+
+        let receipt = notifier
+            .send_message_to_contact(notification, contact)
+            .await
+            .unwrap();
+
+        assert_eq!(receipt.provider_id(), "test");
+        assert_eq!(receipt.provider_message_id(), Some("1"));
+
+        let messages = channel.messages.lock().unwrap();
+        let len = messages.len();
+        assert_eq!(len, 1);
+    }
+
+    #[tokio::test]
+    async fn test_notify_recipient_on_every_channel() {
+        let channel = TestChannel::default();
+
+        let notifier = test_notifier(channel.clone()).build().unwrap();
+
+        let recipient = Recipient::new()
+            .with_contact(TestContact("Destination (1)".to_string()))
+            .with_contact(FailingContact("Destination (2)".to_string()));
+
+        let notification = TestNotification::new(1, "first notification".to_string());
+
+        let report = notifier.notify(recipient, notification).await.unwrap();
+
+        assert_eq!(report.outcomes().len(), 2);
+        assert!(!report.is_success());
+
+        let test_channel_type = <TestChannel as Channel<&'static str>>::channel_type(&channel);
+        assert!(report.get(test_channel_type).unwrap().is_sent());
+
+        let failing_channel_type =
+            <FailingChannel as Channel<&'static str>>::channel_type(&FailingChannel);
+        assert!(report.get(failing_channel_type).unwrap().is_failed());
+
+        assert_eq!(channel.messages.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_notify_skips_channels_without_template() {
+        let notifier = Notifier::<&'static str>::default();
+
+        let channel = TestChannel::default();
+
+        notifier.register_channel(channel.clone()).unwrap();
+        notifier.register_channel(FailingChannel).unwrap();
+
+        notifier
+            .register_notification::<TestNotification, TestTemplate>(TestTemplate(
+                "message = {{message}}",
+            ))
+            .unwrap();
+
+        let recipient = Recipient::new()
+            .with_contact(TestContact("Destination (1)".to_string()))
+            .with_contact(FailingContact("Destination (2)".to_string()));
+
+        let notification = TestNotification::new(1, "first notification".to_string());
+
+        let report = notifier.notify(recipient, notification).await.unwrap();
+
+        assert!(report.is_success());
+
+        let failing_channel_type =
+            <FailingChannel as Channel<&'static str>>::channel_type(&FailingChannel);
+        assert!(matches!(
+            report.get(failing_channel_type),
+            Some(Outcome::Skipped(SkipReason::TemplateNotFound))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_notify_user_with_contact_resolver() {
+        let channel = TestChannel::default();
+
+        let notifier = test_notifier(channel.clone())
+            .configure(|notifier| {
+                notifier.set_contact_resolver(
+                    contact::InMemoryContactResolver::new()
+                        .with_contact("42", TestContact("Destination (42)".to_string())),
+                )
+            })
+            .build()
+            .unwrap();
+
+        let notification = TestNotification::new(1, "first notification".to_string());
+
+        let report = notifier.notify_user("42", notification).await.unwrap();
+
+        assert!(report.is_success());
+
+        let messages = channel.messages.lock().unwrap();
+        assert_eq!(messages[0].contact.0, "Destination (42)");
+    }
+
+    #[tokio::test]
+    async fn test_notify_unknown_user() {
+        let mut notifier = Notifier::<&'static str>::default();
+
+        notifier.set_contact_resolver(contact::InMemoryContactResolver::new());
+
+        let notification = TestNotification::new(1, "first notification".to_string());
+
+        let result = notifier.notify_user("42", notification).await;
+
+        assert!(matches!(
+            result,
+            Err(Error::Contact(ContactError::ContactNotFound(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_notify_skips_opted_out_channel() {
+        let channel = TestChannel::default();
+        let failing_channel_type =
+            <FailingChannel as Channel<&'static str>>::channel_type(&FailingChannel);
+
+        let preferences = preference::InMemoryPreferences::new().with(
+            "42",
+            TestNotification::id(),
+            failing_channel_type,
+            Decision::Skip,
+        );
+
+        let notifier = test_notifier(channel.clone())
+            .configure(|notifier| notifier.set_preference_store(preferences))
+            .build()
+            .unwrap();
+
+        let recipient = Recipient::new()
+            .with_id("42")
+            .with_contact(TestContact("Destination (1)".to_string()))
+            .with_contact(FailingContact("Destination (2)".to_string()));
+
+        let notification = TestNotification::new(1, "first notification".to_string());
+
+        let report = notifier.notify(recipient, notification).await.unwrap();
+
+        assert!(report.is_success());
+        assert!(matches!(
+            report.get(failing_channel_type),
+            Some(Outcome::Skipped(SkipReason::OptedOut))
+        ));
+        assert_eq!(channel.messages.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_notify_redirects_channel() {
+        let channel = TestChannel::default();
+        let test_channel_type = <TestChannel as Channel<&'static str>>::channel_type(&channel);
+        let failing_channel_type =
+            <FailingChannel as Channel<&'static str>>::channel_type(&FailingChannel);
+
+        let preferences = preference::InMemoryPreferences::new().with(
+            "42",
+            TestNotification::id(),
+            failing_channel_type,
+            Decision::Redirect(test_channel_type),
+        );
+
+        let notifier = test_notifier(channel.clone())
+            .configure(|notifier| notifier.set_preference_store(preferences))
+            .build()
+            .unwrap();
+
+        let recipient = Recipient::new()
+            .with_id("42")
+            .with_contact(FailingContact("Destination (2)".to_string()));
+
+        let notification = TestNotification::new(1, "first notification".to_string());
+
+        let report = notifier.notify(recipient, notification).await.unwrap();
+
+        assert!(matches!(
+            report.get(failing_channel_type),
+            Some(Outcome::Redirected(channel_type)) if *channel_type == test_channel_type
+        ));
+        assert!(matches!(
+            report.get(test_channel_type),
+            Some(Outcome::Skipped(SkipReason::MissingContact))
+        ));
+        assert!(channel.messages.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_notify_redirects_to_resolved_contact() {
+        let channel = TestChannel::default();
+        let test_channel_type = <TestChannel as Channel<&'static str>>::channel_type(&channel);
+        let failing_channel_type =
+            <FailingChannel as Channel<&'static str>>::channel_type(&FailingChannel);
+
+        let preferences = preference::InMemoryPreferences::new().with(
+            "42",
+            TestNotification::id(),
+            failing_channel_type,
+            Decision::Redirect(test_channel_type),
+        );
+
+        let mut notifier = test_notifier(channel.clone())
+            .configure(|notifier| notifier.set_preference_store(preferences))
+            .build()
+            .unwrap();
+        notifier.set_contact_resolver(
+            contact::InMemoryContactResolver::new()
+                .with_contact("42", TestContact("Destination (1)".to_string())),
+        );
+
+        // the test channel isn't selected without the redirect
+        let recipient = Recipient::new()
+            .with_id("42")
+            .with_contact(FailingContact("Destination (2)".to_string()));
+
+        let notification = TestNotification::new(1, "first notification".to_string());
+
+        let report = notifier.notify(recipient, notification).await.unwrap();
+
+        assert!(matches!(
+            report.get(failing_channel_type),
+            Some(Outcome::Redirected(channel_type)) if *channel_type == test_channel_type
+        ));
+        assert!(report.get(test_channel_type).unwrap().is_sent());
+
+        let messages = channel.messages.lock().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].contact.0, "Destination (1)");
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn test_records_metrics() {
+        let channel = TestChannel::default();
+        let metrics = metrics::Metrics::new().unwrap();
+
+        let mut notifier = Notifier::<&'static str>::default();
+
+        notifier.register_channel(channel).unwrap();
+        notifier.register_channel(FailingChannel).unwrap();
+        notifier.set_metrics(metrics.clone());
+
+        notifier
+            .register_notification::<TestNotification, TestTemplate>(TestTemplate(
+                "message = {{message}}",
+            ))
+            .unwrap();
+
+        let recipient = Recipient::new()
+            .with_contact(TestContact("Destination (1)".to_string()))
+            .with_contact(FailingContact("Destination (2)".to_string()));
+
+        notifier
+            .notify(
+                recipient,
+                TestNotification::new(1, "first notification".to_string()),
+            )
+            .await
+            .unwrap();
+
+        // the template's variable is missing from the data
+        let result = notifier
+            .send_dynamic(
+                "test_notification",
+                &serde_json::json!({}),
+                "test",
+                serde_json::json!("Destination (1)"),
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(Error::Template(TemplateError::Render(_)))
+        ));
+
+        let output = metrics.render().unwrap();
+
+        assert!(output.contains(r#"notification_id="test_notification",provider="test"} 1"#));
+        assert!(output.contains(r#"reason="template_not_found"} 1"#));
+        assert!(output.contains(
+            r#"notifier_failed_total{channel="test",error_kind="internal",notification_id="test_notification",provider="none",reason="render"} 1"#
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_shared_notifier_updates_at_runtime() {
+        let channel = TestChannel::default();
+
+        let notifier = Arc::new(Notifier::<&'static str>::default());
+        notifier.register_channel(channel.clone()).unwrap();
+        notifier
+            .register_notification::<TestNotification, TestTemplate>(TestTemplate(
+                "message = {{message}}",
+            ))
+            .unwrap();
+
+        let send = |message: &str| {
+            let notifier = Arc::clone(&notifier);
+            let notification = TestNotification::new(1, message.to_string());
+
+            tokio::spawn(async move {
+                notifier
+                    .send_message_to_contact(
+                        notification,
+                        TestContact("Destination (1)".to_string()),
+                    )
+                    .await
+            })
+        };
+
+        send("first").await.unwrap().unwrap();
+
+        // replace the template while the notifier is shared
+        notifier
+            .register_notification::<TestNotification, TestTemplate>(TestTemplate(
+                "replaced = {{message}}",
+            ))
+            .unwrap();
+
+        send("second").await.unwrap().unwrap();
+
+        {
+            let messages = channel.messages.lock().unwrap();
+            assert_eq!(messages[0].contents.output, "message = first");
+            assert_eq!(messages[1].contents.output, "replaced = second");
+        }
+
+        assert!(notifier.remove_notification::<TestNotification>());
+        assert!(matches!(
+            send("third").await.unwrap(),
+            Err(Error::Template(TemplateError::NotFound { .. }))
+        ));
+
+        let channel_type = <TestChannel as Channel<&'static str>>::channel_type(&channel);
+
+        assert!(notifier.remove_channel(channel_type));
+        assert!(matches!(
+            send("fourth").await.unwrap(),
+            Err(Error::UnknownChannel(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_releases_replaced_and_removed_templates() {
+        let notifier = Notifier::<&'static str>::new();
+        let channel = TestChannel::default();
+        let channel_type = <TestChannel as Channel<&'static str>>::channel_type(&channel);
+
+        notifier.register_channel(channel).unwrap();
+
+        let engine_len = || notifier.templates.read().unwrap().engine().len();
+
+        for _ in 0..3 {
+            notifier
+                .register_notification::<TestNotification, TestTemplate>(TestTemplate(
+                    "message = {{message}}",
+                ))
+                .unwrap();
+
+            assert_eq!(engine_len(), 1);
+        }
+
+        assert!(notifier.remove_template::<TestNotification>(channel_type));
+        assert_eq!(engine_len(), 0);
+
+        notifier
+            .register_notification::<TestNotification, TestTemplate>(TestTemplate(
+                "message = {{message}}",
+            ))
+            .unwrap();
+
+        assert!(notifier.remove_channel(channel_type));
+        assert_eq!(engine_len(), 0);
+    }
+
+    #[derive(serde::Serialize)]
+    struct TestMarketingNotification {
+        message: String,
+    }
+
+    impl Notification for TestMarketingNotification {
+        type Id = &'static str;
+
+        fn id() -> Self::Id {
+            TestNotification::id()
+        }
+
+        fn channel_instance(channel: ChannelType) -> Option<&'static str> {
+            match channel.key() {
+                "test" => Some("marketing"),
+                _ => None,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sends_with_named_channel_instance() {
+        let transactional = TestChannel::default();
+        let marketing = TestChannel::default();
+
+        let notifier = Notifier::<&'static str>::default();
+        notifier.register_channel(transactional.clone()).unwrap();
+        notifier
+            .register_channel_instance("marketing", marketing.clone())
+            .unwrap();
+        notifier.register_channel(FailingChannel).unwrap();
+
+        // the template is shared by both instances
+        notifier
+            .register_notification::<TestNotification, TestTemplate>(TestTemplate(
+                "message = {{message}}",
+            ))
+            .unwrap();
+        notifier
+            .register_notification::<TestNotification, FailingTemplate>(FailingTemplate(
+                "message = {{message}}",
+            ))
+            .unwrap();
+
+        notifier
+            .send_message_to_contact(
+                TestNotification::new(1, "transactional".to_string()),
+                TestContact("Destination (1)".to_string()),
+            )
+            .await
+            .unwrap();
+
+        let recipient = Recipient::new()
+            .with_contact(TestContact("Destination (1)".to_string()))
+            .with_contact(FailingContact("Destination (2)".to_string()));
+
+        let report = notifier
+            .notify(
+                recipient,
+                TestMarketingNotification {
+                    message: "marketing".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+
+        let channel_type = <TestChannel as Channel<&'static str>>::channel_type(&marketing);
+        assert!(report.get(channel_type).unwrap().is_sent());
+
+        // the failing channel has no marketing instance and uses its default
+        let failing_channel_type =
+            <FailingChannel as Channel<&'static str>>::channel_type(&FailingChannel);
+        assert!(matches!(
+            report.get(failing_channel_type),
+            Some(Outcome::Failed(Error::Provider(_)))
+        ));
+
+        assert_eq!(
+            transactional.messages.lock().unwrap()[0].contents.output,
+            "message = transactional"
+        );
+        assert_eq!(
+            marketing.messages.lock().unwrap()[0].contents.output,
+            "message = marketing"
+        );
+
+        // removing the default instance keeps the templates for the other
+        assert!(notifier.remove_channel(channel_type));
+        assert!(notifier
+            .templates
+            .read()
+            .unwrap()
+            .has_template(&TestNotification::id(), channel_type));
+    }
+
+    #[tokio::test]
+    async fn test_fails_without_named_channel_instance() {
+        let channel = TestChannel::default();
+        let channel_type = <TestChannel as Channel<&'static str>>::channel_type(&channel);
+
+        let notifier = Notifier::<&'static str>::new();
+        notifier.register_channel(channel.clone()).unwrap();
+        notifier
+            .register_notification::<TestNotification, TestTemplate>(TestTemplate(
+                "message = {{message}}",
+            ))
+            .unwrap();
+
+        let notification = || TestMarketingNotification {
+            message: "marketing".to_string(),
+        };
+
+        let result = notifier
+            .send_message_to_contact(notification(), TestContact("Destination (1)".to_string()))
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(Error::UnknownChannelInstance { ref key, instance: Some(ref instance) })
+                if key == "test" && instance == "marketing"
+        ));
+
+        let recipient = Recipient::new().with_contact(TestContact("Destination (1)".to_string()));
+        let report = notifier.notify(recipient, notification()).await.unwrap();
+
+        assert!(report.get(channel_type).unwrap().is_failed());
+        assert!(channel.messages.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replaces_channel_keeping_templates() {
+        let notifier = Notifier::<&'static str>::default();
+        notifier.register_channel(TestChannel::default()).unwrap();
+
+        notifier
+            .register_notification::<TestNotification, TestTemplate>(TestTemplate(
+                "message = {{message}}",
+            ))
+            .unwrap();
+
+        assert!(matches!(
+            notifier.register_channel(TestChannel::default()),
+            Err(Error::DuplicateChannel { key: "test", .. })
+        ));
+
+        let channel = TestChannel::default();
+        notifier.replace_channel(channel.clone()).unwrap();
+
+        notifier
+            .send_message_to_contact(
+                TestNotification::new(1, "replaced".to_string()),
+                TestContact("Destination (1)".to_string()),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            channel.messages.lock().unwrap()[0].contents.output,
+            "message = replaced"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_preview_message() {
+        let channel = TestChannel::default();
+
+        let notifier = test_notifier(channel.clone()).build().unwrap();
+
+        let preview = notifier
+            .preview(
+                TestNotification::new(1, "preview".to_string()),
+                TestContact("Destination (1)".to_string()),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(preview["contents"]["output"], "message = preview");
+        assert_eq!(preview["contact"], "Destination (1)");
+        assert!(channel.messages.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dry_run() {
+        let channel = TestChannel::default();
+
+        let notifier = test_notifier(channel.clone())
+            .configure(|notifier| notifier.set_dry_run(true))
+            .build()
+            .unwrap();
+
+        let receipt = notifier
+            .send_message_to_contact(
+                TestNotification::new(1, "dry run".to_string()),
+                TestContact("Destination (1)".to_string()),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(receipt.provider_id(), "dry_run");
+        assert_eq!(
+            receipt.metadata()["message"]["contents"]["output"],
+            "message = dry run"
+        );
+        assert!(channel.messages.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_send_dynamic_notification() {
+        let channel = TestChannel::default();
+
+        let notifier = test_notifier(channel.clone()).build().unwrap();
+
+        let data = serde_json::json!({ "message": "dynamic" });
+
+        notifier
+            .send_dynamic(
+                "test_notification",
+                &data,
+                "test",
+                serde_json::json!("Destination (1)"),
+            )
+            .await
+            .unwrap();
+
+        let recipient: DynamicRecipient = serde_json::from_value(serde_json::json!({
+            "id": "user",
+            "contacts": { "test": "Destination (2)" }
+        }))
+        .unwrap();
+
+        let report = notifier
+            .notify_dynamic("test_notification", &data, recipient)
+            .await
+            .unwrap();
+
+        assert!(report.is_success());
+
+        {
+            let messages = channel.messages.lock().unwrap();
+            assert_eq!(messages.len(), 2);
+            assert_eq!(messages[1].contact.0, "Destination (2)");
+            assert_eq!(messages[1].contents.output, "message = dynamic");
+        }
+
+        assert!(matches!(
+            notifier
+                .send_dynamic("unknown", &data, "test", serde_json::json!("Destination"))
+                .await,
+            Err(Error::UnknownNotification(_))
+        ));
+
+        let recipient = DynamicRecipient {
+            contacts: [("sms".to_string(), serde_json::json!("+15555555555"))].into(),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            notifier
+                .notify_dynamic("test_notification", &data, recipient)
+                .await,
+            Err(Error::UnknownChannelKey(_))
+        ));
+    }
+
+    #[derive(serde::Serialize)]
+    struct TestOwnedIdNotification {
+        message: String,
+    }
+
+    impl Notification for TestOwnedIdNotification {
+        type Id = String;
+
+        fn id() -> Self::Id {
+            "owned_id".to_string()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_owned_notification_ids() {
+        let notifier = Notifier::<String>::default();
+
+        let channel = TestChannel::default();
+        notifier.register_channel(channel.clone()).unwrap();
+        notifier
+            .register_notification::<TestOwnedIdNotification, TestTemplate>(TestTemplate(
+                "message = {{message}}",
+            ))
+            .unwrap();
+
+        notifier
+            .send_message_to_contact(
+                TestOwnedIdNotification {
+                    message: "typed".to_string(),
+                },
+                TestContact("Destination (1)".to_string()),
+            )
+            .await
+            .unwrap();
+
+        notifier
+            .send_dynamic(
+                "owned_id",
+                &serde_json::json!({ "message": "dynamic" }),
+                "test",
+                serde_json::json!("Destination (2)"),
+            )
+            .await
+            .unwrap();
+
+        let messages = channel.messages.lock().unwrap();
+        assert_eq!(messages[0].contents.output, "message = typed");
+        assert_eq!(messages[1].contents.output, "message = dynamic");
+        assert_eq!(messages[1].contents.notification_id, "owned_id");
+    }
+
+    /// An id that is neither `Copy` nor `Default`, like a UUID newtype.
+    #[derive(
+        Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+    )]
+    struct TestNewtypeId(u128);
+
+    impl std::fmt::Display for TestNewtypeId {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:032x}", self.0)
+        }
+    }
+
+    #[derive(serde::Serialize)]
+    struct TestNewtypeIdNotification {
+        message: String,
+    }
+
+    impl Notification for TestNewtypeIdNotification {
+        type Id = TestNewtypeId;
+
+        fn id() -> Self::Id {
+            TestNewtypeId(42)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_newtype_notification_ids() {
+        let channel = TestChannel::default();
+
+        let notifier = Notifier::<TestNewtypeId>::builder()
+            .channel(channel.clone())
+            .template::<TestNewtypeIdNotification, TestTemplate>(TestTemplate(
+                "message = {{message}}",
+            ))
+            .build()
+            .unwrap();
+
+        notifier
+            .send_message_to_contact(
+                TestNewtypeIdNotification {
+                    message: "typed".to_string(),
+                },
+                TestContact("Destination (1)".to_string()),
+            )
+            .await
+            .unwrap();
+
+        notifier
+            .send_dynamic(
+                &TestNewtypeId(42).to_string(),
+                &serde_json::json!({ "message": "dynamic" }),
+                "test",
+                serde_json::json!("Destination (2)"),
+            )
+            .await
+            .unwrap();
+
+        let messages = channel.messages.lock().unwrap();
+        assert_eq!(messages[0].contents.output, "message = typed");
+        assert_eq!(messages[1].contents.output, "message = dynamic");
+    }
+
+    #[tokio::test]
+    async fn test_fails_to_register_notification_on_unknown_channel() {
+        let notifier = Notifier::<&'static str>::default();
+        let template = TestTemplate("message = {{message}}");
+
+        let result = notifier.register_notification::<TestNotification, TestTemplate>(template);
+
+        assert!(result.is_err());
+        assert!(matches!(result, Err(Error::UnknownChannel(_))));
+    }
+
+    #[tokio::test]
+    async fn test_fails_to_send_unknown_notification() {
+        let notifier = Notifier::<&'static str>::default();
 
         let channel = TestChannel::default();
 
-        notifier.register_channel(channel.clone());
+        notifier.register_channel(channel.clone()).unwrap();
 
         let notification = TestNotification::new(1, "first notification".to_string());
         let contact = TestContact("Destination (1)".to_string());
@@ -1,14 +1,15 @@
 use std::any::Any;
 
 use erased_serde::Serialize;
+use serde::de::DeserializeOwned;
 
 use crate::channel::ChannelType;
 
-pub trait Message: Any + Serialize + Send {}
+pub trait Message: Any + Serialize + DeserializeOwned + Send {}
 
 // serialize_trait_object!(Message);
 
-impl<T: Any + Serialize + Send> Message for T {}
+impl<T: Any + Serialize + DeserializeOwned + Send> Message for T {}
 
 // static_assertions::assert_obj_safe!(Message);
 
@@ -32,7 +33,21 @@ impl DynMessage {
     }
 
     pub fn message(&self) -> &(dyn Any + Send) {
-        &self.message
+        self.message.as_ref()
+    }
+
+    pub fn message_mut(&mut self) -> &mut (dyn Any + Send) {
+        self.message.as_mut()
+    }
+
+    /// Get a reference to the message if it is of type `M`.
+    pub fn downcast_ref<M: Any>(&self) -> Option<&M> {
+        self.message.downcast_ref()
+    }
+
+    /// Get a mutable reference to the message if it is of type `M`.
+    pub fn downcast_mut<M: Any>(&mut self) -> Option<&mut M> {
+        self.message.downcast_mut()
     }
 
     pub fn take_message(self) -> Box<dyn Any + Send> {
@@ -58,7 +73,7 @@ impl DynMessageContents {
     }
 
     pub fn contents(&self) -> &(dyn Any + Send) {
-        &self.contents
+        self.contents.as_ref()
     }
 
     pub fn take_contents(self) -> Box<dyn Any + Send> {
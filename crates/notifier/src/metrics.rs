@@ -0,0 +1,187 @@
+use std::time::Duration;
+
+use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry, TextEncoder};
+
+use crate::{
+    channel::ChannelType,
+    receipt::DeliveryReceipt,
+    report::{FailureReason, SkipReason},
+    Error,
+};
+
+/// Prometheus metrics for the notifier's sends, labelled by the notification
+/// id, channel and provider. Failures are also labelled by the step that
+/// failed, e.g. rendering or the provider's send.
+///
+/// The metrics are cheap to clone, keep a clone to render them after setting
+/// them on the notifier.
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    sent: IntCounterVec,
+    failed: IntCounterVec,
+    skipped: IntCounterVec,
+    latency: HistogramVec,
+}
+
+impl Metrics {
+    /// Create the metrics in their own registry.
+    pub fn new() -> Result<Self, prometheus::Error> {
+        Self::with_registry(Registry::new())
+    }
+
+    /// Create the metrics and register them with the registry, e.g. to render
+    /// them along with the application's own metrics.
+    pub fn with_registry(registry: Registry) -> Result<Self, prometheus::Error> {
+        let sent = IntCounterVec::new(
+            Opts::new("notifier_sent_total", "Messages accepted by a provider"),
+            &["notification_id", "channel", "provider"],
+        )?;
+        let failed = IntCounterVec::new(
+            Opts::new("notifier_failed_total", "Messages that failed to send"),
+            &[
+                "notification_id",
+                "channel",
+                "provider",
+                "error_kind",
+                "reason",
+            ],
+        )?;
+        let skipped = IntCounterVec::new(
+            Opts::new("notifier_skipped_total", "Channels skipped when notifying"),
+            &["notification_id", "channel", "reason"],
+        )?;
+        let latency = HistogramVec::new(
+            HistogramOpts::new(
+                "notifier_send_duration_seconds",
+                "Time taken to send a message with the channel's provider",
+            ),
+            &["notification_id", "channel", "provider"],
+        )?;
+
+        registry.register(Box::new(sent.clone()))?;
+        registry.register(Box::new(failed.clone()))?;
+        registry.register(Box::new(skipped.clone()))?;
+        registry.register(Box::new(latency.clone()))?;
+
+        Ok(Self {
+            registry,
+            sent,
+            failed,
+            skipped,
+            latency,
+        })
+    }
+
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// Render the registry's metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> Result<String, prometheus::Error> {
+        TextEncoder::new().encode_to_string(&self.registry.gather())
+    }
+
+    pub(crate) fn record_send(
+        &self,
+        notification_id: &str,
+        channel_type: ChannelType,
+        elapsed: Duration,
+        result: &Result<DeliveryReceipt, Error>,
+    ) {
+        let channel = channel_type.key();
+
+        let provider = match result {
+            Ok(receipt) => {
+                self.sent
+                    .with_label_values(&[notification_id, channel, receipt.provider_id()])
+                    .inc();
+
+                receipt.provider_id()
+            }
+            Err(e) => {
+                let (provider, kind) = match e {
+                    Error::Provider(e) => (e.provider_id(), e.kind().as_str()),
+                    _ => ("unknown", "internal"),
+                };
+
+                self.failed
+                    .with_label_values(&[
+                        notification_id,
+                        channel,
+                        provider,
+                        kind,
+                        FailureReason::Send.as_str(),
+                    ])
+                    .inc();
+
+                provider
+            }
+        };
+
+        self.latency
+            .with_label_values(&[notification_id, channel, provider])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Record a failure before the message reached the provider, so it has
+    /// no provider or latency.
+    pub(crate) fn record_failure(
+        &self,
+        notification_id: &str,
+        channel: &str,
+        reason: FailureReason,
+    ) {
+        self.failed
+            .with_label_values(&[
+                notification_id,
+                channel,
+                "none",
+                "internal",
+                reason.as_str(),
+            ])
+            .inc();
+    }
+
+    pub(crate) fn record_skip(
+        &self,
+        notification_id: &str,
+        channel_type: ChannelType,
+        reason: SkipReason,
+    ) {
+        self.skipped
+            .with_label_values(&[notification_id, channel_type.key(), reason.as_str()])
+            .inc();
+    }
+}
+
+#[cfg(test)]
+mod test_metrics {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::new().unwrap();
+        let channel_type = ChannelType::new("test");
+
+        metrics.record_send(
+            "welcome",
+            channel_type,
+            Duration::from_millis(5),
+            &Ok(DeliveryReceipt::new("smtp")),
+        );
+        metrics.record_skip("welcome", channel_type, SkipReason::OptedOut);
+        metrics.record_failure("welcome", "test", FailureReason::Render);
+
+        let output = metrics.render().unwrap();
+
+        assert!(output.contains(
+            r#"notifier_sent_total{channel="test",notification_id="welcome",provider="smtp"} 1"#
+        ));
+        assert!(output.contains(r#"reason="opted_out"} 1"#));
+        assert!(output.contains(
+            r#"notifier_failed_total{channel="test",error_kind="internal",notification_id="welcome",provider="none",reason="render"} 1"#
+        ));
+        assert!(output.contains("notifier_send_duration_seconds_count"));
+    }
+}
@@ -0,0 +1,193 @@
+use async_trait::async_trait;
+
+use crate::{channel::ChannelType, message::DynMessage, receipt::DeliveryReceipt, Error, Id};
+
+/// The channel and notification that a message is being rendered or sent
+/// for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HookContext<I: Id> {
+    pub channel_type: ChannelType,
+    pub notification_id: I,
+}
+
+/// Hooks that run around rendering and sending each message.
+///
+/// The notifier runs the `before_*` hooks of its middleware in the order they
+/// were added and the `after_*` and `on_error` hooks in reverse, so that the
+/// first middleware wraps all of the others. Returning an error from a hook
+/// stops the message from being sent and the error is reported like a failed
+/// send. A hook blocks a message with [`Error::Blocked`].
+#[async_trait]
+pub trait Middleware<I: Id>: Sync + Send + 'static {
+    async fn before_render(&self, _context: &HookContext<I>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Called with the message created from the rendered template.
+    async fn after_render(
+        &self,
+        _context: &HookContext<I>,
+        _message: &mut DynMessage,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Called with the message that is about to be sent, including messages
+    /// that were rendered earlier and sent from the outbox or schedule.
+    async fn before_send(
+        &self,
+        _context: &HookContext<I>,
+        _message: &mut DynMessage,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn after_send(&self, _context: &HookContext<I>, _receipt: &DeliveryReceipt) {}
+
+    /// Called when rendering or sending fails, including failures caused by
+    /// another middleware's hook.
+    async fn on_error(&self, _context: &HookContext<I>, _error: &Error) {}
+}
+
+static_assertions::assert_obj_safe!(Middleware<&'static str>);
+
+#[cfg(test)]
+mod test_middleware {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::{testing::*, Notifier};
+
+    #[derive(Clone, Default)]
+    struct RecordingMiddleware {
+        name: &'static str,
+        events: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl RecordingMiddleware {
+        fn record(&self, event: &str) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("{}:{event}", self.name));
+        }
+    }
+
+    #[async_trait]
+    impl Middleware<&'static str> for RecordingMiddleware {
+        async fn before_render(&self, _: &HookContext<&'static str>) -> Result<(), Error> {
+            self.record("before_render");
+            Ok(())
+        }
+
+        async fn after_render(
+            &self,
+            _: &HookContext<&'static str>,
+            _: &mut DynMessage,
+        ) -> Result<(), Error> {
+            self.record("after_render");
+            Ok(())
+        }
+
+        async fn before_send(
+            &self,
+            context: &HookContext<&'static str>,
+            message: &mut DynMessage,
+        ) -> Result<(), Error> {
+            self.record("before_send");
+
+            let message = message.downcast_mut::<TestMessage>().unwrap();
+
+            if message.contact.0 == "blocked" {
+                return Err(Error::Blocked("the contact is blocked".to_string()));
+            }
+
+            message.contents.output += &format!(" ({}, {})", self.name, context.notification_id);
+
+            Ok(())
+        }
+
+        async fn after_send(&self, _: &HookContext<&'static str>, _: &DeliveryReceipt) {
+            self.record("after_send");
+        }
+
+        async fn on_error(&self, _: &HookContext<&'static str>, _: &Error) {
+            self.record("on_error");
+        }
+    }
+
+    /// Add an outer and an inner middleware that record their events.
+    fn add_middleware(notifier: &mut Notifier<&'static str>, events: &Arc<Mutex<Vec<String>>>) {
+        for name in ["outer", "inner"] {
+            notifier.add_middleware(RecordingMiddleware {
+                name,
+                events: Arc::clone(events),
+            });
+        }
+    }
+
+    #[tokio::test]
+    async fn test_runs_around_render_and_send() {
+        let channel = TestChannel::default();
+        let events = Arc::default();
+
+        let notifier = test_notifier(channel.clone())
+            .configure(|notifier| add_middleware(notifier, &events))
+            .build()
+            .unwrap();
+
+        notifier
+            .send_message_to_contact(
+                TestNotification::new(1, "first".to_string()),
+                TestContact("Destination (1)".to_string()),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                "outer:before_render",
+                "inner:before_render",
+                "inner:after_render",
+                "outer:after_render",
+                "outer:before_send",
+                "inner:before_send",
+                "inner:after_send",
+                "outer:after_send",
+            ]
+        );
+
+        let messages = channel.messages.lock().unwrap();
+        assert_eq!(
+            messages[0].contents.output,
+            "message = first (outer, test_notification) (inner, test_notification)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_blocks_message() {
+        let channel = TestChannel::default();
+        let events = Arc::default();
+
+        let notifier = test_notifier(channel.clone())
+            .configure(|notifier| add_middleware(notifier, &events))
+            .build()
+            .unwrap();
+
+        let result = notifier
+            .send_message_to_contact(
+                TestNotification::new(1, "first".to_string()),
+                TestContact("blocked".to_string()),
+            )
+            .await;
+
+        assert!(matches!(result, Err(Error::Blocked(_))));
+        assert!(channel.messages.lock().unwrap().is_empty());
+
+        assert_eq!(
+            events.lock().unwrap()[4..],
+            ["outer:before_send", "inner:on_error", "outer:on_error"]
+        );
+    }
+}
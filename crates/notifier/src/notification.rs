@@ -2,11 +2,15 @@ use std::any::Any;
 
 use serde::{Deserialize, Serialize};
 
+use crate::channel::ChannelType;
+
+/// The id of a notification. Ids are stored in persisted records as their
+/// `Display` form, so owned ids such as `String`, `Arc<str>` or newtypes work
+/// as well as `&'static str` and integers.
 pub trait Id:
     std::fmt::Debug
     + std::fmt::Display
     + Clone
-    + Copy
     + PartialEq
     + Eq
     + PartialOrd
@@ -24,7 +28,6 @@ impl<T> Id for T where
     T: std::fmt::Debug
         + std::fmt::Display
         + Clone
-        + Copy
         + PartialEq
         + Eq
         + PartialOrd
@@ -38,10 +41,72 @@ impl<T> Id for T where
 {
 }
 
+/// How urgent a notification is. Critical notifications are sent during the
+/// recipient's quiet hours, all others are deferred until the hours end.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Critical,
+}
+
+/// A template source embedded in the binary, e.g. with
+/// `#[derive(Notification)]`. Channels build their templates from the parts
+/// embedded for their key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmbeddedTemplate {
+    /// The key of the channel that the template is for.
+    pub channel: &'static str,
+    /// The part of the channel's template, e.g. an email's `subject`.
+    pub part: &'static str,
+    pub source: &'static str,
+}
+
 pub trait Notification: Sized + Any + Serialize {
     type Id: Id;
 
     fn id() -> Self::Id
     where
         Self: Sized;
+
+    fn priority(&self) -> Priority {
+        Priority::Normal
+    }
+
+    /// The name of the channel's instance that the notification is sent
+    /// with, or `None` for its default instance. Sending fails on the channel
+    /// when it has no instance of the name.
+    fn channel_instance(_channel: ChannelType) -> Option<&'static str>
+    where
+        Self: Sized,
+    {
+        None
+    }
+
+    /// The keys of the channels that the notification is sent on. A
+    /// [`NotifierBuilder`](crate::NotifierBuilder) requires a template for
+    /// each of them.
+    fn channels() -> &'static [&'static str]
+    where
+        Self: Sized,
+    {
+        &[]
+    }
+
+    /// The templates embedded for the notification.
+    fn templates() -> &'static [EmbeddedTemplate]
+    where
+        Self: Sized,
+    {
+        &[]
+    }
 }
+
+static_assertions::assert_impl_all!(&'static str: Id);
+static_assertions::assert_impl_all!(String: Id);
+static_assertions::assert_impl_all!(std::sync::Arc<str>: Id);
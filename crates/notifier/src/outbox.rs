@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::Error;
+
+pub mod memory;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod worker;
+
+pub use memory::InMemoryOutbox;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteOutbox;
+pub use worker::{OutboxWorker, WorkerOptions};
+
+/// How long the stores remember the ids of sent messages, unless they're
+/// given another retention.
+pub const DEFAULT_SENT_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// A rendered message waiting in the outbox to be sent by its channel.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutboxMessage {
+    pub id: Uuid,
+    /// The key of the channel that sends the message.
+    pub channel: String,
+    /// The name of the channel's instance, `None` for its default instance.
+    #[serde(default)]
+    pub instance: Option<String>,
+    pub notification_id: String,
+    /// The channel's message serialized as JSON.
+    pub payload: serde_json::Value,
+    /// The number of times the message has been leased.
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    /// The message isn't leased before this time.
+    pub available_at: DateTime<Utc>,
+    pub enqueued_at: DateTime<Utc>,
+}
+
+impl OutboxMessage {
+    pub fn new(
+        channel: impl Into<String>,
+        notification_id: impl Into<String>,
+        payload: serde_json::Value,
+    ) -> Self {
+        let now = Utc::now();
+
+        Self {
+            id: Uuid::new_v4(),
+            channel: channel.into(),
+            instance: None,
+            notification_id: notification_id.into(),
+            payload,
+            attempts: 0,
+            last_error: None,
+            available_at: now,
+            enqueued_at: now,
+        }
+    }
+
+    /// Send the message with the named instance of the channel.
+    pub fn with_instance(mut self, instance: Option<&str>) -> Self {
+        self.instance = instance.map(ToOwned::to_owned);
+        self
+    }
+}
+
+/// Durable storage for the messages waiting to be sent.
+///
+/// Workers lease the available messages, which hides them from other workers
+/// until the lease expires, and then either acknowledge, release or dead
+/// letter each message.
+#[async_trait]
+pub trait OutboxStore: Sync + Send + 'static {
+    /// Add the message to the outbox. Does nothing if a message with the id
+    /// is already in the outbox, pending, sent or dead lettered, so that
+    /// enqueuing it again after a crash doesn't send it twice.
+    async fn enqueue(&self, message: OutboxMessage) -> Result<(), Error>;
+
+    /// Lease up to `limit` of the available messages for the duration,
+    /// incrementing their attempts.
+    async fn lease(&self, limit: usize, lease: Duration) -> Result<Vec<OutboxMessage>, Error>;
+
+    /// Mark the message as sent. Its id is remembered for the store's
+    /// retention, after which enqueuing it again sends it again.
+    async fn ack(&self, id: Uuid) -> Result<(), Error>;
+
+    /// Release the message so that it can be leased again after `retry_at`.
+    async fn release(&self, id: Uuid, error: String, retry_at: DateTime<Utc>) -> Result<(), Error>;
+
+    /// Move the message to the dead letters, it won't be leased again.
+    async fn dead_letter(&self, id: Uuid, error: String) -> Result<(), Error>;
+
+    /// Get the messages that have been dead lettered.
+    async fn dead_letters(&self) -> Result<Vec<OutboxMessage>, Error>;
+}
+
+static_assertions::assert_obj_safe!(OutboxStore);
+
+#[cfg(test)]
+mod test_outbox {
+    use super::*;
+    use crate::testing::*;
+
+    #[tokio::test]
+    async fn test_message_requires_registered_notification() {
+        let channel = TestChannel::default();
+
+        let notifier = test_notifier(channel.clone()).build().unwrap();
+
+        let message = OutboxMessage::new(
+            "test",
+            "removed",
+            serde_json::to_value(TestMessage {
+                contact: TestContact("Destination (1)".to_string()),
+                contents: TestMessageContents {
+                    output: "message = removed".to_string(),
+                    notification_id: "removed".to_string(),
+                },
+            })
+            .unwrap(),
+        );
+
+        let result = notifier.send_outbox_message(&message).await;
+
+        assert!(matches!(result, Err(Error::UnknownNotification(_))));
+        assert!(channel.messages.lock().unwrap().is_empty());
+    }
+}
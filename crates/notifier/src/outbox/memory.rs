@@ -0,0 +1,205 @@
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::{OutboxMessage, OutboxStore, DEFAULT_SENT_RETENTION};
+use crate::Error;
+
+#[derive(Default)]
+struct State {
+    pending: HashMap<Uuid, OutboxMessage>,
+    dead: Vec<OutboxMessage>,
+    /// The ids of the sent messages, with when they're forgotten.
+    sent: HashMap<Uuid, DateTime<Utc>>,
+}
+
+/// Outbox that keeps the messages in memory, they are lost when the process
+/// exits.
+pub struct InMemoryOutbox {
+    retention: Duration,
+    state: Mutex<State>,
+}
+
+impl Default for InMemoryOutbox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InMemoryOutbox {
+    pub fn new() -> Self {
+        Self {
+            retention: DEFAULT_SENT_RETENTION,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Set how long the ids of sent messages are remembered.
+    pub fn with_retention(mut self, retention: Duration) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// The number of messages waiting to be sent, including leased ones.
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl OutboxStore for InMemoryOutbox {
+    async fn enqueue(&self, message: OutboxMessage) -> Result<(), Error> {
+        let now = Utc::now();
+        let mut state = self.state.lock().unwrap();
+
+        state.sent.retain(|_, forget_at| *forget_at > now);
+
+        if !state.sent.contains_key(&message.id)
+            && !state.dead.iter().any(|dead| dead.id == message.id)
+        {
+            state.pending.entry(message.id).or_insert(message);
+        }
+
+        Ok(())
+    }
+
+    async fn lease(&self, limit: usize, lease: Duration) -> Result<Vec<OutboxMessage>, Error> {
+        let now = Utc::now();
+        let lease = chrono::Duration::from_std(lease).map_err(|e| Error::Store(e.into()))?;
+
+        let mut state = self.state.lock().unwrap();
+
+        let mut available = state
+            .pending
+            .values_mut()
+            .filter(|m| m.available_at <= now)
+            .collect::<Vec<_>>();
+
+        available.sort_by_key(|m| (m.available_at, m.enqueued_at));
+
+        let leased = available
+            .into_iter()
+            .take(limit)
+            .map(|message| {
+                message.attempts += 1;
+                message.available_at = now + lease;
+                message.clone()
+            })
+            .collect();
+
+        Ok(leased)
+    }
+
+    async fn ack(&self, id: Uuid) -> Result<(), Error> {
+        let retention =
+            chrono::Duration::from_std(self.retention).map_err(|e| Error::Store(e.into()))?;
+
+        let mut state = self.state.lock().unwrap();
+
+        if state.pending.remove(&id).is_some() {
+            state.sent.insert(id, Utc::now() + retention);
+        }
+        Ok(())
+    }
+
+    async fn release(&self, id: Uuid, error: String, retry_at: DateTime<Utc>) -> Result<(), Error> {
+        if let Some(message) = self.state.lock().unwrap().pending.get_mut(&id) {
+            message.last_error = Some(error);
+            message.available_at = retry_at;
+        }
+        Ok(())
+    }
+
+    async fn dead_letter(&self, id: Uuid, error: String) -> Result<(), Error> {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(mut message) = state.pending.remove(&id) {
+            message.last_error = Some(error);
+            state.dead.push(message);
+        }
+
+        Ok(())
+    }
+
+    async fn dead_letters(&self) -> Result<Vec<OutboxMessage>, Error> {
+        Ok(self.state.lock().unwrap().dead.clone())
+    }
+}
+
+#[cfg(test)]
+mod test_in_memory_outbox {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_leased_messages_are_hidden() {
+        let outbox = InMemoryOutbox::new();
+
+        let message = OutboxMessage::new("channel", "notification", serde_json::json!({}));
+        outbox.enqueue(message.clone()).await.unwrap();
+
+        let leased = outbox.lease(10, Duration::from_secs(30)).await.unwrap();
+        assert_eq!(leased.len(), 1);
+        assert_eq!(leased[0].attempts, 1);
+
+        let leased = outbox.lease(10, Duration::from_secs(30)).await.unwrap();
+        assert!(leased.is_empty());
+
+        outbox
+            .release(message.id, "failed".to_owned(), Utc::now())
+            .await
+            .unwrap();
+
+        let leased = outbox.lease(10, Duration::from_secs(30)).await.unwrap();
+        assert_eq!(leased[0].attempts, 2);
+        assert_eq!(leased[0].last_error.as_deref(), Some("failed"));
+
+        outbox.ack(message.id).await.unwrap();
+        assert!(outbox.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_enqueue_ignores_existing_id() {
+        let outbox = InMemoryOutbox::new();
+
+        let message = OutboxMessage::new("channel", "notification", serde_json::json!({}));
+        outbox.enqueue(message.clone()).await.unwrap();
+        outbox.lease(10, Duration::from_secs(30)).await.unwrap();
+
+        outbox.enqueue(message.clone()).await.unwrap();
+
+        assert_eq!(outbox.len(), 1);
+        assert!(outbox
+            .lease(10, Duration::from_secs(30))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_enqueue_ignores_sent_id() {
+        let outbox = InMemoryOutbox::new();
+
+        let message = OutboxMessage::new("channel", "notification", serde_json::json!({}));
+        outbox.enqueue(message.clone()).await.unwrap();
+        outbox.lease(10, Duration::from_secs(30)).await.unwrap();
+        outbox.ack(message.id).await.unwrap();
+
+        outbox.enqueue(message.clone()).await.unwrap();
+        assert!(outbox.is_empty());
+
+        // the id is forgotten after the retention
+        let outbox = InMemoryOutbox::new().with_retention(Duration::ZERO);
+
+        outbox.enqueue(message.clone()).await.unwrap();
+        outbox.ack(message.id).await.unwrap();
+
+        outbox.enqueue(message).await.unwrap();
+        assert_eq!(outbox.len(), 1);
+    }
+}
@@ -0,0 +1,313 @@
+use std::{path::Path, sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use uuid::Uuid;
+
+use super::{OutboxMessage, OutboxStore, DEFAULT_SENT_RETENTION};
+use crate::{
+    sqlite::{from_millis, parse_json, parse_uuid, store_error, to_millis},
+    Error,
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS notifier_outbox (
+    id TEXT PRIMARY KEY NOT NULL,
+    channel TEXT NOT NULL,
+    notification_id TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    attempts INTEGER NOT NULL,
+    last_error TEXT,
+    available_at INTEGER NOT NULL,
+    enqueued_at INTEGER NOT NULL,
+    dead INTEGER NOT NULL DEFAULT 0,
+    instance TEXT,
+    -- when the message was sent, its id is kept for the retention
+    sent_at INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS notifier_outbox_available
+    ON notifier_outbox (dead, available_at);
+";
+
+const COLUMNS: &str = "id, channel, notification_id, payload, attempts, last_error, available_at, \
+                       enqueued_at, instance";
+
+/// Outbox that persists the messages in a SQLite database. Queries run on the
+/// calling task, which is fine for SQLite's short lived writes.
+pub struct SqliteOutbox {
+    retention: Duration,
+    conn: Mutex<Connection>,
+}
+
+impl SqliteOutbox {
+    /// Open the database at the path, creating the outbox's table if it
+    /// doesn't exist.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let conn = Connection::open(path).map_err(store_error)?;
+        Self::from_connection(conn)
+    }
+
+    /// Open an in-memory database, mostly useful for tests.
+    pub fn open_in_memory() -> Result<Self, Error> {
+        let conn = Connection::open_in_memory().map_err(store_error)?;
+        Self::from_connection(conn)
+    }
+
+    /// Use the connection, creating the outbox's table if it doesn't exist.
+    pub fn from_connection(conn: Connection) -> Result<Self, Error> {
+        conn.execute_batch(SCHEMA).map_err(store_error)?;
+
+        Ok(Self {
+            retention: DEFAULT_SENT_RETENTION,
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Set how long the ids of sent messages are remembered.
+    pub fn with_retention(mut self, retention: Duration) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Get the message, whether it is pending, sent or dead lettered.
+    pub fn get(&self, id: Uuid) -> Result<Option<OutboxMessage>, Error> {
+        self.conn
+            .lock()
+            .unwrap()
+            .query_row(
+                &format!("SELECT {COLUMNS} FROM notifier_outbox WHERE id = ?1"),
+                params![id.to_string()],
+                from_row,
+            )
+            .optional()
+            .map_err(store_error)
+    }
+}
+
+fn from_row(row: &Row) -> rusqlite::Result<OutboxMessage> {
+    let id: String = row.get(0)?;
+    let payload: String = row.get(3)?;
+
+    Ok(OutboxMessage {
+        id: parse_uuid(0, &id)?,
+        channel: row.get(1)?,
+        instance: row.get(8)?,
+        notification_id: row.get(2)?,
+        payload: parse_json(3, &payload)?,
+        attempts: row.get(4)?,
+        last_error: row.get(5)?,
+        available_at: from_millis(row.get(6)?)?,
+        enqueued_at: from_millis(row.get(7)?)?,
+    })
+}
+
+#[async_trait]
+impl OutboxStore for SqliteOutbox {
+    async fn enqueue(&self, message: OutboxMessage) -> Result<(), Error> {
+        let payload = serde_json::to_string(&message.payload).map_err(Error::Serde)?;
+
+        self.conn
+            .lock()
+            .unwrap()
+            .execute(
+                &format!(
+                    "INSERT OR IGNORE INTO notifier_outbox ({COLUMNS})
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"
+                ),
+                params![
+                    message.id.to_string(),
+                    message.channel,
+                    message.notification_id,
+                    payload,
+                    message.attempts,
+                    message.last_error,
+                    to_millis(message.available_at),
+                    to_millis(message.enqueued_at),
+                    message.instance,
+                ],
+            )
+            .map_err(store_error)?;
+
+        Ok(())
+    }
+
+    async fn lease(&self, limit: usize, lease: Duration) -> Result<Vec<OutboxMessage>, Error> {
+        let now = Utc::now();
+        let lease = chrono::Duration::from_std(lease).map_err(|e| Error::Store(e.into()))?;
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction().map_err(store_error)?;
+
+        let mut messages = {
+            let mut stmt = tx
+                .prepare(&format!(
+                    "SELECT {COLUMNS} FROM notifier_outbox
+                    WHERE dead = 0 AND sent_at IS NULL AND available_at <= ?1
+                    ORDER BY available_at, enqueued_at
+                    LIMIT ?2"
+                ))
+                .map_err(store_error)?;
+
+            let rows = stmt
+                .query_map(params![to_millis(now), limit as i64], from_row)
+                .map_err(store_error)?;
+
+            rows.collect::<Result<Vec<_>, _>>().map_err(store_error)?
+        };
+
+        for message in messages.iter_mut() {
+            message.attempts += 1;
+            message.available_at = now + lease;
+
+            tx.execute(
+                "UPDATE notifier_outbox SET attempts = ?2, available_at = ?3 WHERE id = ?1",
+                params![
+                    message.id.to_string(),
+                    message.attempts,
+                    to_millis(message.available_at)
+                ],
+            )
+            .map_err(store_error)?;
+        }
+
+        tx.commit().map_err(store_error)?;
+
+        Ok(messages)
+    }
+
+    async fn ack(&self, id: Uuid) -> Result<(), Error> {
+        let retention =
+            chrono::Duration::from_std(self.retention).map_err(|e| Error::Store(e.into()))?;
+        let now = Utc::now();
+
+        let conn = self.conn.lock().unwrap();
+
+        conn.execute(
+            "UPDATE notifier_outbox SET sent_at = ?2 WHERE id = ?1 AND dead = 0",
+            params![id.to_string(), to_millis(now)],
+        )
+        .map_err(store_error)?;
+
+        // forget the messages sent before the retention
+        conn.execute(
+            "DELETE FROM notifier_outbox WHERE sent_at <= ?1",
+            params![to_millis(now - retention)],
+        )
+        .map_err(store_error)?;
+
+        Ok(())
+    }
+
+    async fn release(&self, id: Uuid, error: String, retry_at: DateTime<Utc>) -> Result<(), Error> {
+        self.conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE notifier_outbox SET last_error = ?2, available_at = ?3 WHERE id = ?1",
+                params![id.to_string(), error, to_millis(retry_at)],
+            )
+            .map_err(store_error)?;
+
+        Ok(())
+    }
+
+    async fn dead_letter(&self, id: Uuid, error: String) -> Result<(), Error> {
+        self.conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE notifier_outbox SET last_error = ?2, dead = 1 WHERE id = ?1",
+                params![id.to_string(), error],
+            )
+            .map_err(store_error)?;
+
+        Ok(())
+    }
+
+    async fn dead_letters(&self) -> Result<Vec<OutboxMessage>, Error> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {COLUMNS} FROM notifier_outbox WHERE dead = 1 ORDER BY enqueued_at"
+            ))
+            .map_err(store_error)?;
+
+        let rows = stmt.query_map([], from_row).map_err(store_error)?;
+
+        rows.collect::<Result<Vec<_>, _>>().map_err(store_error)
+    }
+}
+
+#[cfg(test)]
+mod test_sqlite_outbox {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_lease_release_and_dead_letter() {
+        let outbox = SqliteOutbox::open_in_memory().unwrap();
+
+        let message = OutboxMessage::new("channel", "notification", serde_json::json!({"a": 1}));
+        outbox.enqueue(message.clone()).await.unwrap();
+
+        let leased = outbox.lease(10, Duration::from_secs(30)).await.unwrap();
+        assert_eq!(leased.len(), 1);
+        assert_eq!(leased[0].attempts, 1);
+        assert_eq!(leased[0].payload, message.payload);
+
+        assert!(outbox
+            .lease(10, Duration::from_secs(30))
+            .await
+            .unwrap()
+            .is_empty());
+
+        outbox
+            .release(message.id, "failed".to_owned(), Utc::now())
+            .await
+            .unwrap();
+
+        let leased = outbox.lease(10, Duration::from_secs(30)).await.unwrap();
+        assert_eq!(leased[0].attempts, 2);
+        assert_eq!(leased[0].last_error.as_deref(), Some("failed"));
+
+        outbox
+            .dead_letter(message.id, "gave up".to_owned())
+            .await
+            .unwrap();
+
+        let dead = outbox.dead_letters().await.unwrap();
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].last_error.as_deref(), Some("gave up"));
+
+        // enqueuing it again doesn't fail or revive it
+        outbox.enqueue(message.clone()).await.unwrap();
+        assert_eq!(outbox.dead_letters().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_enqueue_ignores_sent_id() {
+        let outbox = SqliteOutbox::open_in_memory().unwrap();
+
+        let message = OutboxMessage::new("channel", "notification", serde_json::json!({"a": 1}));
+        outbox.enqueue(message.clone()).await.unwrap();
+        outbox.lease(10, Duration::from_secs(30)).await.unwrap();
+        outbox.ack(message.id).await.unwrap();
+
+        outbox.enqueue(message.clone()).await.unwrap();
+        assert!(outbox.lease(10, Duration::ZERO).await.unwrap().is_empty());
+
+        // the id is forgotten after the retention
+        let outbox = SqliteOutbox::open_in_memory()
+            .unwrap()
+            .with_retention(Duration::ZERO);
+
+        outbox.enqueue(message.clone()).await.unwrap();
+        outbox.ack(message.id).await.unwrap();
+        assert!(outbox.get(message.id).unwrap().is_none());
+
+        outbox.enqueue(message).await.unwrap();
+        assert_eq!(outbox.lease(10, Duration::ZERO).await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,198 @@
+use std::{future::Future, time::Duration};
+
+use chrono::Utc;
+
+use super::OutboxMessage;
+use crate::{provider::RetryPolicy, Error, Id, Notifier};
+
+#[derive(Debug, Clone)]
+pub struct WorkerOptions {
+    /// The maximum number of messages leased at once.
+    pub batch_size: usize,
+    /// How long a leased message is hidden from other workers.
+    pub lease: Duration,
+    /// How long to wait before polling the outbox when it was empty.
+    pub poll_interval: Duration,
+    /// The number of attempts and backoff between them before a message is
+    /// dead lettered.
+    pub retry: RetryPolicy,
+}
+
+impl Default for WorkerOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: 10,
+            lease: Duration::from_secs(30),
+            poll_interval: Duration::from_secs(1),
+            retry: RetryPolicy::default()
+                .with_max_attempts(5)
+                .with_initial_backoff(Duration::from_secs(1))
+                .with_max_backoff(Duration::from_secs(300)),
+        }
+    }
+}
+
+/// Sends the messages in the notifier's outbox.
+///
+/// Messages that fail with a transient error are released to be retried
+/// after a backoff, all other failures and messages that ran out of attempts
+/// are dead lettered.
+pub struct OutboxWorker<'a, I: Id> {
+    notifier: &'a Notifier<I>,
+    options: WorkerOptions,
+}
+
+impl<'a, I: Id> OutboxWorker<'a, I> {
+    pub fn new(notifier: &'a Notifier<I>, options: WorkerOptions) -> Self {
+        Self { notifier, options }
+    }
+
+    /// Lease a batch of messages and send them, returning the number of
+    /// messages that were leased.
+    pub async fn run_once(&self) -> Result<usize, Error> {
+        let outbox = self.notifier.outbox().ok_or(Error::NoOutbox)?;
+
+        let messages = outbox
+            .lease(self.options.batch_size, self.options.lease)
+            .await?;
+        let leased = messages.len();
+
+        for message in messages {
+            self.deliver(message).await?;
+        }
+
+        Ok(leased)
+    }
+
+    /// Keep sending the outbox's messages until the shutdown future
+    /// completes. Returns early if the outbox fails.
+    pub async fn run_until<F: Future<Output = ()>>(&self, shutdown: F) -> Result<(), Error> {
+        tokio::pin!(shutdown);
+
+        loop {
+            let leased = self.run_once().await?;
+
+            // keep going straight away while there are more messages to send
+            let wait = if leased < self.options.batch_size {
+                self.options.poll_interval
+            } else {
+                Duration::ZERO
+            };
+
+            tokio::select! {
+                biased;
+                _ = &mut shutdown => return Ok(()),
+                _ = tokio::time::sleep(wait) => {}
+            }
+        }
+    }
+
+    async fn deliver(&self, message: OutboxMessage) -> Result<(), Error> {
+        let outbox = self.notifier.outbox().ok_or(Error::NoOutbox)?;
+
+        match self.notifier.send_outbox_message(&message).await {
+            Ok(_) => outbox.ack(message.id).await,
+            Err(e) if e.is_transient() && message.attempts < self.options.retry.max_attempts() => {
+                let backoff = self.options.retry.backoff(message.attempts);
+                let retry_at = Utc::now()
+                    + chrono::Duration::from_std(backoff).map_err(|e| Error::Store(e.into()))?;
+
+                outbox.release(message.id, e.to_string(), retry_at).await
+            }
+            Err(e) => outbox.dead_letter(message.id, e.to_string()).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_outbox_worker {
+    use super::*;
+    use crate::{outbox::InMemoryOutbox, testing::*};
+
+    fn create_notifier(channel: TestChannel) -> Notifier<&'static str> {
+        let mut notifier = Notifier::<&'static str>::default();
+
+        notifier.register_channel(channel).unwrap();
+        notifier.register_channel(FailingChannel).unwrap();
+        notifier.set_outbox(InMemoryOutbox::new());
+
+        notifier
+            .register_notification::<TestNotification, TestTemplate>(TestTemplate(
+                "message = {{message}}",
+            ))
+            .unwrap();
+        notifier
+            .register_notification::<TestNotification, FailingTemplate>(FailingTemplate(
+                "message = {{message}}",
+            ))
+            .unwrap();
+
+        notifier
+    }
+
+    #[tokio::test]
+    async fn test_sends_enqueued_messages() {
+        let channel = TestChannel::default();
+        let notifier = create_notifier(channel.clone());
+
+        let notification = TestNotification::new(1, "first notification".to_string());
+        let contact = TestContact("Destination (1)".to_string());
+
+        notifier
+            .enqueue_message_to_contact(notification, contact)
+            .await
+            .unwrap();
+
+        assert!(channel.messages.lock().unwrap().is_empty());
+
+        let worker = OutboxWorker::new(&notifier, WorkerOptions::default());
+
+        assert_eq!(worker.run_once().await.unwrap(), 1);
+        assert_eq!(worker.run_once().await.unwrap(), 0);
+
+        let messages = channel.messages.lock().unwrap();
+        assert_eq!(messages[0].contents.output, "message = first notification");
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_doesnt_send_enqueued_messages() {
+        let channel = TestChannel::default();
+        let mut notifier = create_notifier(channel.clone());
+
+        notifier
+            .enqueue_message_to_contact(
+                TestNotification::new(1, "first notification".to_string()),
+                TestContact("Destination (1)".to_string()),
+            )
+            .await
+            .unwrap();
+
+        notifier.set_dry_run(true);
+
+        let worker = OutboxWorker::new(&notifier, WorkerOptions::default());
+
+        assert_eq!(worker.run_once().await.unwrap(), 1);
+        assert!(channel.messages.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dead_letters_permanent_failures() {
+        let notifier = create_notifier(TestChannel::default());
+
+        let notification = TestNotification::new(1, "first notification".to_string());
+        let contact = FailingContact("Destination (1)".to_string());
+
+        let id = notifier
+            .enqueue_message_to_contact(notification, contact)
+            .await
+            .unwrap();
+
+        let worker = OutboxWorker::new(&notifier, WorkerOptions::default());
+        worker.run_once().await.unwrap();
+
+        let dead = notifier.outbox().unwrap().dead_letters().await.unwrap();
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].id, id);
+        assert!(dead[0].last_error.is_some());
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+
+use crate::{channel::ChannelType, Error, Id};
+
+/// The decision made about sending a notification on a channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    /// Send the notification on the channel.
+    Allow,
+    /// The recipient opted out of the notification on the channel.
+    Skip,
+    /// Send the notification on the other channel instead, even if the
+    /// recipient wasn't going to be notified on it.
+    Redirect(ChannelType),
+}
+
+/// Stores the recipients' preferences for which channels they want to
+/// receive notifications on.
+#[async_trait]
+pub trait PreferenceStore<I: Id>: Sync + Send + 'static {
+    async fn decide(
+        &self,
+        recipient_id: &str,
+        notification_id: &I,
+        channel_type: ChannelType,
+    ) -> Result<Decision, Error>;
+}
+
+static_assertions::assert_obj_safe!(PreferenceStore<&'static str>);
+
+/// Preference store that keeps the preferences in memory. Notifications are
+/// allowed on every channel unless a decision has been set for it.
+pub struct InMemoryPreferences<I: Id> {
+    decisions: HashMap<(String, I, ChannelType), Decision>,
+}
+
+impl<I: Id> Default for InMemoryPreferences<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I: Id> InMemoryPreferences<I> {
+    pub fn new() -> Self {
+        Self {
+            decisions: HashMap::new(),
+        }
+    }
+
+    /// Set the decision for the recipient's notification on the channel.
+    pub fn set(
+        &mut self,
+        recipient_id: impl Into<String>,
+        notification_id: I,
+        channel_type: ChannelType,
+        decision: Decision,
+    ) {
+        self.decisions.insert(
+            (recipient_id.into(), notification_id, channel_type),
+            decision,
+        );
+    }
+
+    /// Set the decision for the recipient's notification on the channel.
+    pub fn with(
+        mut self,
+        recipient_id: impl Into<String>,
+        notification_id: I,
+        channel_type: ChannelType,
+        decision: Decision,
+    ) -> Self {
+        self.set(recipient_id, notification_id, channel_type, decision);
+        self
+    }
+}
+
+#[async_trait]
+impl<I: Id> PreferenceStore<I> for InMemoryPreferences<I> {
+    async fn decide(
+        &self,
+        recipient_id: &str,
+        notification_id: &I,
+        channel_type: ChannelType,
+    ) -> Result<Decision, Error> {
+        let decision = self
+            .decisions
+            .get(&(
+                recipient_id.to_owned(),
+                notification_id.clone(),
+                channel_type,
+            ))
+            .copied()
+            .unwrap_or(Decision::Allow);
+
+        Ok(decision)
+    }
+}
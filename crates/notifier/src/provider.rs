@@ -1,7 +1,16 @@
 pub mod error;
-pub use error::Error;
+pub mod failover;
+pub mod retry;
 
-use crate::message::Message;
+pub use error::{Error, ErrorKind};
+pub use failover::Failover;
+pub use retry::{Retry, RetryPolicy};
+
+use std::time::Instant;
+
+use tracing::{field, Instrument};
+
+use crate::{message::Message, receipt::DeliveryReceipt};
 
 #[async_trait::async_trait]
 pub trait Provider: Sync + Send + 'static {
@@ -9,7 +18,40 @@ pub trait Provider: Sync + Send + 'static {
 
     fn id(&self) -> &'static str;
 
-    async fn send(&self, message: Self::Message) -> Result<(), Error>;
+    async fn send(&self, message: Self::Message) -> Result<DeliveryReceipt, Error>;
+}
+
+/// Send the message with the provider inside a span that records the
+/// provider's id, the outcome and the latency. Channels should send through
+/// this rather than calling [`Provider::send`] directly.
+pub async fn send_instrumented<P: Provider + ?Sized>(
+    provider: &P,
+    message: P::Message,
+) -> Result<DeliveryReceipt, Error> {
+    let span = tracing::info_span!(
+        "provider_send",
+        provider_id = provider.id(),
+        outcome = field::Empty,
+        error_kind = field::Empty,
+        latency_ms = field::Empty,
+    );
+
+    let start = Instant::now();
+    let result = provider.send(message).instrument(span.clone()).await;
+
+    span.record("latency_ms", start.elapsed().as_millis() as u64);
+
+    match &result {
+        Ok(_) => {
+            span.record("outcome", "sent");
+        }
+        Err(e) => {
+            span.record("outcome", "failed");
+            span.record("error_kind", e.kind().as_str());
+        }
+    }
+
+    result
 }
 
 mod assertions {
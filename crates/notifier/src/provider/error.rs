@@ -1,3 +1,37 @@
+use std::time::Duration;
+
+/// Classifies a provider error so that the caller can decide whether to retry,
+/// suppress or alert on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// Sending the message again later may succeed.
+    Transient,
+    /// Sending the message again will fail.
+    Permanent,
+    /// The provider is limiting the rate of messages, sending again should
+    /// wait at least `retry_after` when it is known.
+    RateLimited { retry_after: Option<Duration> },
+    /// The recipient can't receive messages, e.g. the mailbox doesn't exist.
+    InvalidRecipient,
+}
+
+impl ErrorKind {
+    /// Whether sending the message again may succeed.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, ErrorKind::Transient | ErrorKind::RateLimited { .. })
+    }
+
+    /// A short name for the kind, used in logs.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::Transient => "transient",
+            ErrorKind::Permanent => "permanent",
+            ErrorKind::RateLimited { .. } => "rate_limited",
+            ErrorKind::InvalidRecipient => "invalid_recipient",
+        }
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("ContactError")]
@@ -10,12 +44,19 @@ pub enum Error {
 
     #[error("SendError")]
     Send {
+        kind: ErrorKind,
         channel_id: &'static str,
         provider_id: &'static str,
         source: anyhow::Error,
         context: Option<&'static str>,
     },
 
+    #[error("Provider timed out after {timeout:?}")]
+    Timeout {
+        provider_id: &'static str,
+        timeout: Duration,
+    },
+
     #[error("Provider had unknown error")]
     Unknown {
         channel_id: &'static str,
@@ -24,3 +65,39 @@ pub enum Error {
         context: Option<&'static str>,
     },
 }
+
+impl Error {
+    /// Classify the error. Contact errors are caused by an invalid recipient
+    /// and unknown errors are assumed to be permanent.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::Contact { .. } => ErrorKind::InvalidRecipient,
+            Error::Send { kind, .. } => *kind,
+            Error::Timeout { .. } => ErrorKind::Transient,
+            Error::Unknown { .. } => ErrorKind::Permanent,
+        }
+    }
+
+    /// Whether the error is transient, meaning that sending the message again
+    /// may succeed.
+    pub fn is_transient(&self) -> bool {
+        self.kind().is_retryable()
+    }
+
+    /// How long the provider asked to wait before sending again.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self.kind() {
+            ErrorKind::RateLimited { retry_after } => retry_after,
+            _ => None,
+        }
+    }
+
+    pub fn provider_id(&self) -> &'static str {
+        match self {
+            Error::Contact { provider_id, .. }
+            | Error::Send { provider_id, .. }
+            | Error::Timeout { provider_id, .. }
+            | Error::Unknown { provider_id, .. } => provider_id,
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use async_trait::async_trait;
+
+use super::{send_instrumented, Error, Provider};
+use crate::{message::Message, receipt::DeliveryReceipt};
+
+/// The order that a [`Failover`] tries its providers in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// Always start with the first provider.
+    Ordered,
+    /// Rotate the provider that is started with, proportionally to the
+    /// providers' weights.
+    WeightedRoundRobin,
+}
+
+struct Weighted<M> {
+    provider: Box<dyn Provider<Message = M>>,
+    weight: usize,
+}
+
+/// Provider that sends the message with the first of its providers to
+/// succeed. It only falls through to the next provider on transient errors,
+/// other errors are returned straight away.
+pub struct Failover<M: Message> {
+    providers: Vec<Weighted<M>>,
+    strategy: Strategy,
+    next: AtomicUsize,
+}
+
+impl<M: Message + Clone + Sync> Failover<M> {
+    /// Create a failover that tries the providers in the order they were
+    /// added, starting with the `primary` provider.
+    pub fn new(primary: impl Provider<Message = M>) -> Self {
+        Self {
+            providers: vec![Weighted {
+                provider: Box::new(primary),
+                weight: 1,
+            }],
+            strategy: Strategy::Ordered,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Set the strategy used to pick the provider to start with.
+    pub fn with_strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Add the provider with a weight of 1.
+    pub fn with_provider(self, provider: impl Provider<Message = M>) -> Self {
+        self.with_weighted_provider(provider, 1)
+    }
+
+    /// Add the provider with the weight, which is only used by
+    /// [`Strategy::WeightedRoundRobin`].
+    pub fn with_weighted_provider(
+        mut self,
+        provider: impl Provider<Message = M>,
+        weight: usize,
+    ) -> Self {
+        self.providers.push(Weighted {
+            provider: Box::new(provider),
+            weight: weight.max(1),
+        });
+        self
+    }
+
+    pub fn strategy(&self) -> Strategy {
+        self.strategy
+    }
+
+    /// The index of the provider that the next message starts with.
+    fn start(&self) -> usize {
+        if self.strategy == Strategy::Ordered {
+            return 0;
+        }
+
+        let total: usize = self.providers.iter().map(|p| p.weight).sum();
+        let mut slot = self.next.fetch_add(1, Ordering::Relaxed) % total;
+
+        for (index, weighted) in self.providers.iter().enumerate() {
+            if slot < weighted.weight {
+                return index;
+            }
+            slot -= weighted.weight;
+        }
+
+        0
+    }
+}
+
+#[async_trait]
+impl<M: Message + Clone + Sync> Provider for Failover<M> {
+    type Message = M;
+
+    fn id(&self) -> &'static str {
+        "failover"
+    }
+
+    async fn send(&self, message: Self::Message) -> Result<DeliveryReceipt, Error> {
+        let start = self.start();
+        let len = self.providers.len();
+
+        let mut attempts = 0;
+
+        loop {
+            let provider = &self.providers[(start + attempts) % len].provider;
+            attempts += 1;
+
+            match send_instrumented(provider.as_ref(), message.clone()).await {
+                Ok(receipt) => return Ok(receipt.with_metadata("failover_attempts", attempts)),
+                Err(e) if e.is_transient() && attempts < len => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_failover {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use super::*;
+    use crate::provider::ErrorKind;
+
+    #[derive(Clone)]
+    struct StaticProvider {
+        id: &'static str,
+        error: Option<ErrorKind>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl StaticProvider {
+        fn new(id: &'static str, error: Option<ErrorKind>) -> Self {
+            Self {
+                id,
+                error,
+                calls: Arc::default(),
+            }
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl Provider for StaticProvider {
+        type Message = String;
+
+        fn id(&self) -> &'static str {
+            self.id
+        }
+
+        async fn send(&self, _message: String) -> Result<DeliveryReceipt, Error> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+
+            match self.error {
+                Some(kind) => Err(Error::Send {
+                    kind,
+                    channel_id: "test",
+                    provider_id: self.id,
+                    source: anyhow::Error::msg("static provider failed"),
+                    context: None,
+                }),
+                None => Ok(DeliveryReceipt::new(self.id)),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_falls_through_on_transient_error() {
+        let primary = StaticProvider::new("primary", Some(ErrorKind::Transient));
+        let secondary = StaticProvider::new("secondary", None);
+
+        let failover = Failover::new(primary.clone()).with_provider(secondary.clone());
+
+        let receipt = failover.send("message".to_owned()).await.unwrap();
+
+        assert_eq!(receipt.provider_id(), "secondary");
+        assert_eq!(receipt.metadata()["failover_attempts"], 2);
+        assert_eq!(primary.calls(), 1);
+        assert_eq!(secondary.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_returns_permanent_error() {
+        let primary = StaticProvider::new("primary", Some(ErrorKind::InvalidRecipient));
+        let secondary = StaticProvider::new("secondary", None);
+
+        let failover = Failover::new(primary).with_provider(secondary.clone());
+
+        let result = failover.send("message".to_owned()).await;
+
+        assert!(matches!(result, Err(e) if e.kind() == ErrorKind::InvalidRecipient));
+        assert_eq!(secondary.calls(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_returns_last_error_when_every_provider_fails() {
+        let primary = StaticProvider::new("primary", Some(ErrorKind::Transient));
+        let secondary = StaticProvider::new("secondary", Some(ErrorKind::Transient));
+
+        let failover = Failover::new(primary).with_provider(secondary);
+
+        let result = failover.send("message".to_owned()).await;
+
+        assert!(matches!(result, Err(e) if e.provider_id() == "secondary"));
+    }
+
+    #[tokio::test]
+    async fn test_weighted_round_robin() {
+        let primary = StaticProvider::new("primary", None);
+        let secondary = StaticProvider::new("secondary", None);
+
+        let failover = Failover::new(primary.clone())
+            .with_weighted_provider(secondary.clone(), 3)
+            .with_strategy(Strategy::WeightedRoundRobin);
+
+        for _ in 0..8 {
+            failover.send("message".to_owned()).await.unwrap();
+        }
+
+        assert_eq!(primary.calls(), 2);
+        assert_eq!(secondary.calls(), 6);
+    }
+}
@@ -0,0 +1,322 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use super::{send_instrumented, Error, Provider};
+use crate::receipt::DeliveryReceipt;
+
+/// Policy for retrying the messages that a provider failed to send.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+    jitter: f64,
+    attempt_timeout: Option<Duration>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            multiplier: 2.0,
+            jitter: 0.5,
+            attempt_timeout: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of attempts, including the first one.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Set the backoff before the second attempt.
+    pub fn with_initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// Set the upper bound of the backoff between attempts.
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Set the factor that the backoff grows by after each attempt.
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    /// Set the fraction of the backoff, between 0 and 1, that is randomly
+    /// subtracted from it.
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set the time that a single attempt is allowed to take.
+    pub fn with_attempt_timeout(mut self, attempt_timeout: Duration) -> Self {
+        self.attempt_timeout = Some(attempt_timeout);
+        self
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    pub fn attempt_timeout(&self) -> Option<Duration> {
+        self.attempt_timeout
+    }
+
+    /// The backoff to wait after the attempt failed, with jitter applied.
+    /// It's never more than the max backoff.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+
+        let backoff = self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent);
+        let backoff = backoff.min(self.max_backoff.as_secs_f64());
+
+        let jitter = backoff * self.jitter * fastrand::f64();
+
+        // the float overflows or isn't finite for large backoffs
+        Duration::try_from_secs_f64(backoff - jitter)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff)
+    }
+}
+
+/// Wraps a provider and retries sending the message when the provider fails
+/// with a transient error.
+pub struct Retry<P: Provider> {
+    inner: P,
+    policy: RetryPolicy,
+}
+
+impl<P: Provider> Retry<P> {
+    pub fn new(inner: P, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    async fn attempt(&self, message: P::Message) -> Result<DeliveryReceipt, Error> {
+        match self.policy.attempt_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, send_instrumented(&self.inner, message))
+                .await
+                .unwrap_or(Err(Error::Timeout {
+                    provider_id: self.inner.id(),
+                    timeout,
+                })),
+            None => send_instrumented(&self.inner, message).await,
+        }
+    }
+}
+
+#[async_trait]
+impl<P> Provider for Retry<P>
+where
+    P: Provider,
+    P::Message: Clone + Sync,
+{
+    type Message = P::Message;
+
+    fn id(&self) -> &'static str {
+        self.inner.id()
+    }
+
+    async fn send(&self, message: Self::Message) -> Result<DeliveryReceipt, Error> {
+        let mut attempt = 1;
+
+        loop {
+            match self.attempt(message.clone()).await {
+                Ok(receipt) => return Ok(receipt.with_metadata("attempts", attempt)),
+                Err(e) if e.is_transient() && attempt < self.policy.max_attempts => {
+                    // wait at least as long as a rate limiting provider asked
+                    let backoff = self.policy.backoff(attempt);
+                    let backoff = e.retry_after().map_or(backoff, |d| d.max(backoff));
+
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_retry {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+    use crate::provider::ErrorKind;
+
+    /// Provider that fails with the error until it has been called `failures`
+    /// times.
+    struct FlakyProvider {
+        failures: u32,
+        calls: AtomicU32,
+        kind: ErrorKind,
+        delay: Option<Duration>,
+    }
+
+    impl FlakyProvider {
+        fn new(failures: u32, kind: ErrorKind) -> Self {
+            Self {
+                failures,
+                calls: AtomicU32::new(0),
+                kind,
+                delay: None,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Provider for FlakyProvider {
+        type Message = String;
+
+        fn id(&self) -> &'static str {
+            "flaky"
+        }
+
+        async fn send(&self, _message: String) -> Result<DeliveryReceipt, Error> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+
+            if let Some(delay) = self.delay {
+                tokio::time::sleep(delay).await;
+            }
+
+            if call > self.failures {
+                return Ok(DeliveryReceipt::new("flaky"));
+            }
+
+            Err(Error::Send {
+                kind: self.kind,
+                channel_id: "test",
+                provider_id: "flaky",
+                source: anyhow::Error::msg("flaky provider failed"),
+                context: None,
+            })
+        }
+    }
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy::new()
+            .with_max_attempts(3)
+            .with_initial_backoff(Duration::from_millis(1))
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_errors() {
+        let retry = Retry::new(FlakyProvider::new(2, ErrorKind::Transient), policy());
+
+        let receipt = retry.send("message".to_owned()).await.unwrap();
+
+        assert_eq!(retry.inner().calls.load(Ordering::SeqCst), 3);
+        assert_eq!(receipt.metadata()["attempts"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_attempts() {
+        let retry = Retry::new(FlakyProvider::new(5, ErrorKind::Transient), policy());
+
+        let result = retry.send("message".to_owned()).await;
+
+        assert!(matches!(result, Err(Error::Send { .. })));
+        assert_eq!(retry.inner().calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_does_not_retry_permanent_errors() {
+        let retry = Retry::new(FlakyProvider::new(1, ErrorKind::InvalidRecipient), policy());
+
+        let result = retry.send("message".to_owned()).await;
+
+        assert!(matches!(
+            result,
+            Err(Error::Send {
+                kind: ErrorKind::InvalidRecipient,
+                ..
+            })
+        ));
+        assert_eq!(retry.inner().calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_times_out_attempts() {
+        let mut provider = FlakyProvider::new(0, ErrorKind::Transient);
+        provider.delay = Some(Duration::from_secs(10));
+
+        let retry = Retry::new(
+            provider,
+            policy().with_attempt_timeout(Duration::from_millis(5)),
+        );
+
+        let result = retry.send("message".to_owned()).await;
+
+        assert!(matches!(result, Err(Error::Timeout { .. })));
+        assert_eq!(retry.inner().calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_waits_for_rate_limit() {
+        let kind = ErrorKind::RateLimited {
+            retry_after: Some(Duration::from_millis(20)),
+        };
+        let retry = Retry::new(FlakyProvider::new(1, kind), policy());
+
+        let started = std::time::Instant::now();
+        retry.send("message".to_owned()).await.unwrap();
+
+        assert!(started.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn test_backoff_grows_exponentially() {
+        let policy = RetryPolicy::new()
+            .with_initial_backoff(Duration::from_millis(100))
+            .with_max_backoff(Duration::from_millis(350))
+            .with_jitter(0.0);
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(350));
+    }
+
+    #[test]
+    fn test_backoff_is_clamped_for_high_attempts() {
+        let policy = RetryPolicy::new().with_jitter(0.0);
+
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_secs(10));
+
+        let policy = RetryPolicy::new()
+            .with_max_backoff(Duration::MAX)
+            .with_jitter(0.0);
+
+        assert_eq!(policy.backoff(2000), Duration::MAX);
+        assert_eq!(policy.backoff(u32::MAX), Duration::MAX);
+
+        // with jitter
+        assert!(RetryPolicy::new().backoff(u32::MAX) <= Duration::from_secs(10));
+        RetryPolicy::new()
+            .with_max_backoff(Duration::MAX)
+            .backoff(u32::MAX);
+    }
+}
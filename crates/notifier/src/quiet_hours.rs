@@ -0,0 +1,118 @@
+use chrono::{DateTime, Duration, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+/// A daily window in the recipient's local time during which non-critical
+/// notifications are held. The window wraps around midnight when it ends
+/// before it starts, e.g. 22:00 to 07:00.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuietHours {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl QuietHours {
+    /// The window includes the start and excludes the end. A window that
+    /// starts and ends at the same time is empty.
+    pub fn new(start: NaiveTime, end: NaiveTime) -> Self {
+        Self { start, end }
+    }
+
+    pub fn start(&self) -> NaiveTime {
+        self.start
+    }
+
+    pub fn end(&self) -> NaiveTime {
+        self.end
+    }
+
+    /// Check if the local time is inside the window.
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    /// When the time is inside the window in the timezone, get the time that
+    /// the window ends.
+    pub fn ends_after(&self, now: DateTime<Utc>, timezone: Tz) -> Option<DateTime<Utc>> {
+        let local = now.with_timezone(&timezone);
+        let time = local.time();
+
+        if !self.contains(time) {
+            return None;
+        }
+
+        let mut date = local.date_naive();
+
+        // the window started yesterday and ends today unless it wraps around
+        // midnight and started today
+        if self.start > self.end && time >= self.start {
+            date = date.succ_opt()?;
+        }
+
+        let end = date.and_time(self.end);
+
+        // when the end falls in a DST gap the window ends once the clocks
+        // have gone forward
+        let end = timezone.from_local_datetime(&end).earliest().or_else(|| {
+            timezone
+                .from_local_datetime(&(end + Duration::hours(1)))
+                .earliest()
+        })?;
+
+        Some(end.with_timezone(&Utc))
+    }
+}
+
+#[cfg(test)]
+mod test_quiet_hours {
+    use super::*;
+
+    fn time(hour: u32, min: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, min, 0).unwrap()
+    }
+
+    #[test]
+    fn test_contains() {
+        let overnight = QuietHours::new(time(22, 0), time(7, 0));
+
+        assert!(overnight.contains(time(23, 30)));
+        assert!(overnight.contains(time(6, 59)));
+        assert!(!overnight.contains(time(7, 0)));
+        assert!(!overnight.contains(time(12, 0)));
+
+        let afternoon = QuietHours::new(time(13, 0), time(14, 0));
+
+        assert!(afternoon.contains(time(13, 0)));
+        assert!(!afternoon.contains(time(14, 0)));
+
+        assert!(!QuietHours::new(time(9, 0), time(9, 0)).contains(time(9, 0)));
+    }
+
+    #[test]
+    fn test_ends_after_in_timezone() {
+        let quiet_hours = QuietHours::new(time(22, 0), time(7, 0));
+        let timezone = chrono_tz::America::New_York;
+
+        // 23:00 in New York (EST, UTC-5)
+        let now = Utc.with_ymd_and_hms(2022, 1, 11, 4, 0, 0).unwrap();
+        assert_eq!(
+            quiet_hours.ends_after(now, timezone),
+            Some(Utc.with_ymd_and_hms(2022, 1, 11, 12, 0, 0).unwrap())
+        );
+
+        // 05:00 in New York
+        let now = Utc.with_ymd_and_hms(2022, 1, 11, 10, 0, 0).unwrap();
+        assert_eq!(
+            quiet_hours.ends_after(now, timezone),
+            Some(Utc.with_ymd_and_hms(2022, 1, 11, 12, 0, 0).unwrap())
+        );
+
+        // 12:00 in New York
+        let now = Utc.with_ymd_and_hms(2022, 1, 11, 17, 0, 0).unwrap();
+        assert_eq!(quiet_hours.ends_after(now, timezone), None);
+    }
+}
@@ -0,0 +1,63 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Receipt returned by a provider once it has accepted a message for
+/// delivery.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeliveryReceipt {
+    provider_id: String,
+    provider_message_id: Option<String>,
+    accepted_at: DateTime<Utc>,
+    metadata: Map<String, Value>,
+}
+
+impl DeliveryReceipt {
+    /// Create a receipt for a message that the provider accepted just now.
+    pub fn new(provider_id: impl Into<String>) -> Self {
+        Self {
+            provider_id: provider_id.into(),
+            provider_message_id: None,
+            accepted_at: Utc::now(),
+            metadata: Map::new(),
+        }
+    }
+
+    /// Set the id that the provider assigned to the message.
+    pub fn with_provider_message_id(mut self, id: impl Into<String>) -> Self {
+        self.provider_message_id = Some(id.into());
+        self
+    }
+
+    /// Set the time that the provider accepted the message.
+    pub fn with_accepted_at(mut self, accepted_at: DateTime<Utc>) -> Self {
+        self.accepted_at = accepted_at;
+        self
+    }
+
+    /// Add the raw metadata returned by the provider.
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    /// Get a reference to the id of the provider that accepted the message.
+    pub fn provider_id(&self) -> &str {
+        self.provider_id.as_ref()
+    }
+
+    /// Get a reference to the id that the provider assigned to the message.
+    pub fn provider_message_id(&self) -> Option<&str> {
+        self.provider_message_id.as_deref()
+    }
+
+    /// Get the time that the provider accepted the message.
+    pub fn accepted_at(&self) -> DateTime<Utc> {
+        self.accepted_at
+    }
+
+    /// Get a reference to the raw metadata returned by the provider.
+    pub fn metadata(&self) -> &Map<String, Value> {
+        &self.metadata
+    }
+}
@@ -0,0 +1,73 @@
+//! Redacts the recipients' addresses before they are recorded in spans.
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Format the contact for a span, redacting every string in it unless
+/// `reveal` is set.
+pub(crate) fn contact<C: Serialize>(contact: &C, reveal: bool) -> String {
+    let value = match serde_json::to_value(contact) {
+        Ok(value) => value,
+        Err(_) => return "<unserializable>".to_owned(),
+    };
+
+    if reveal {
+        value.to_string()
+    } else {
+        redact_value(value).to_string()
+    }
+}
+
+fn redact_value(value: Value) -> Value {
+    match value {
+        Value::String(s) => Value::String(redact(&s)),
+        Value::Array(values) => Value::Array(values.into_iter().map(redact_value).collect()),
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (key, redact_value(value)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+/// Keep the first character and domain of email addresses and the last two
+/// characters of anything else long enough to still be hidden.
+pub(crate) fn redact(address: &str) -> String {
+    if let Some((local, domain)) = address.split_once('@') {
+        let first = local.chars().next().map(String::from).unwrap_or_default();
+        return format!("{first}***@{domain}");
+    }
+
+    let len = address.chars().count();
+
+    if len > 6 {
+        let last = address.chars().skip(len - 2).collect::<String>();
+        format!("***{last}")
+    } else {
+        "***".to_owned()
+    }
+}
+
+#[cfg(test)]
+mod test_redact {
+    use super::*;
+
+    #[test]
+    fn test_redact() {
+        assert_eq!(redact("jane.doe@example.com"), "j***@example.com");
+        assert_eq!(redact("+15551234567"), "***67");
+        assert_eq!(redact("short"), "***");
+    }
+
+    #[test]
+    fn test_contact() {
+        let value = serde_json::json!({"name": "Jane Doe", "email": "jane@example.com"});
+
+        assert_eq!(
+            contact(&value, false),
+            r#"{"email":"j***@example.com","name":"***oe"}"#
+        );
+        assert_eq!(contact(&value, true), value.to_string());
+    }
+}
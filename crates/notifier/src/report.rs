@@ -0,0 +1,179 @@
+use crate::{
+    channel::ChannelType, receipt::DeliveryReceipt, schedule::ScheduleHandle, Error, TemplateError,
+};
+
+/// The reason a channel was skipped when notifying a recipient.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// A template hasn't been registered for the channel and notification.
+    TemplateNotFound,
+    /// The recipient opted out of the notification on the channel.
+    OptedOut,
+    /// The notification was redirected to the channel but the recipient
+    /// doesn't have a contact for it.
+    MissingContact,
+}
+
+impl SkipReason {
+    /// A short name for the reason, used in logs and metrics.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SkipReason::TemplateNotFound => "template_not_found",
+            SkipReason::OptedOut => "opted_out",
+            SkipReason::MissingContact => "missing_contact",
+        }
+    }
+}
+
+/// The step that failed when sending a message, recorded in the metrics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FailureReason {
+    /// The channel or its instance isn't registered.
+    ChannelResolution,
+    /// A template hasn't been registered for the channel and notification.
+    TemplateLookup,
+    /// The template or the message couldn't be rendered.
+    Render,
+    /// A middleware's hook failed or blocked the message.
+    Middleware,
+    /// The channel's provider failed to send the message.
+    #[cfg_attr(not(feature = "metrics"), allow(dead_code))]
+    Send,
+}
+
+impl FailureReason {
+    /// The reason that a rendering error failed with.
+    pub(crate) fn of_render(error: &Error) -> Self {
+        match error {
+            Error::Template(TemplateError::NotFound { .. } | TemplateError::UnknownTemplate(_)) => {
+                FailureReason::TemplateLookup
+            }
+            _ => FailureReason::Render,
+        }
+    }
+
+    /// A short name for the reason, used in metrics.
+    #[cfg_attr(not(feature = "metrics"), allow(dead_code))]
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            FailureReason::ChannelResolution => "channel_resolution",
+            FailureReason::TemplateLookup => "template_lookup",
+            FailureReason::Render => "render",
+            FailureReason::Middleware => "middleware",
+            FailureReason::Send => "send",
+        }
+    }
+}
+
+/// The outcome of sending a notification on a single channel.
+#[derive(Debug)]
+pub enum Outcome {
+    Sent(DeliveryReceipt),
+    Skipped(SkipReason),
+    /// The recipient's preferences redirected the notification to another
+    /// channel.
+    Redirected(ChannelType),
+    /// The notification was scheduled to be sent once the recipient's quiet
+    /// hours end.
+    Deferred(ScheduleHandle),
+    Failed(Error),
+}
+
+impl Outcome {
+    /// A short name for the outcome, used in logs.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Outcome::Sent(_) => "sent",
+            Outcome::Skipped(_) => "skipped",
+            Outcome::Redirected(_) => "redirected",
+            Outcome::Deferred(_) => "deferred",
+            Outcome::Failed(_) => "failed",
+        }
+    }
+
+    pub fn is_sent(&self) -> bool {
+        matches!(self, Self::Sent(_))
+    }
+
+    pub fn is_skipped(&self) -> bool {
+        matches!(self, Self::Skipped(_))
+    }
+
+    pub fn is_deferred(&self) -> bool {
+        matches!(self, Self::Deferred(_))
+    }
+
+    pub fn is_failed(&self) -> bool {
+        matches!(self, Self::Failed(_))
+    }
+}
+
+#[derive(Debug)]
+pub struct ChannelOutcome {
+    pub channel_type: ChannelType,
+    pub outcome: Outcome,
+}
+
+/// Report of the outcome on each channel that a recipient was notified on.
+#[derive(Debug, Default)]
+pub struct Report {
+    outcomes: Vec<ChannelOutcome>,
+}
+
+impl Report {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, channel_type: ChannelType, outcome: Outcome) {
+        self.outcomes.push(ChannelOutcome {
+            channel_type,
+            outcome,
+        });
+    }
+
+    /// Check if the report has an outcome for the channel.
+    pub fn contains(&self, channel_type: ChannelType) -> bool {
+        self.outcomes.iter().any(|o| o.channel_type == channel_type)
+    }
+
+    /// Get the outcome for the channel, if the recipient was notified on it.
+    pub fn get(&self, channel_type: ChannelType) -> Option<&Outcome> {
+        self.outcomes
+            .iter()
+            .find(|o| o.channel_type == channel_type)
+            .map(|o| &o.outcome)
+    }
+
+    pub fn outcomes(&self) -> &[ChannelOutcome] {
+        &self.outcomes
+    }
+
+    pub fn into_outcomes(self) -> Vec<ChannelOutcome> {
+        self.outcomes
+    }
+
+    /// True if the notification was sent on at least one channel and didn't
+    /// fail on any.
+    pub fn is_success(&self) -> bool {
+        self.outcomes.iter().any(|o| o.outcome.is_sent())
+            && !self.outcomes.iter().any(|o| o.outcome.is_failed())
+    }
+
+    /// Iterate over the channels that sent the notification along with their
+    /// receipt.
+    pub fn receipts(&self) -> impl Iterator<Item = (ChannelType, &DeliveryReceipt)> {
+        self.outcomes.iter().filter_map(|o| match &o.outcome {
+            Outcome::Sent(receipt) => Some((o.channel_type, receipt)),
+            _ => None,
+        })
+    }
+
+    /// Iterate over the channels that failed along with their error.
+    pub fn failures(&self) -> impl Iterator<Item = (ChannelType, &Error)> {
+        self.outcomes.iter().filter_map(|o| match &o.outcome {
+            Outcome::Failed(e) => Some((o.channel_type, e)),
+            _ => None,
+        })
+    }
+}
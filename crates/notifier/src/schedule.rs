@@ -0,0 +1,287 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::Error;
+
+pub mod memory;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod worker;
+
+pub use memory::InMemoryScheduleStore;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteScheduleStore;
+pub use worker::{ScheduleWorker, ScheduleWorkerOptions};
+
+/// A rendered message that is sent by its channel once it is due.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledJob {
+    pub id: Uuid,
+    /// The key of the channel that sends the message.
+    pub channel: String,
+    /// The name of the channel's instance, `None` for its default instance.
+    #[serde(default)]
+    pub instance: Option<String>,
+    pub notification_id: String,
+    /// The channel's message serialized as JSON.
+    pub payload: serde_json::Value,
+    /// The number of times the job has been leased.
+    #[serde(default)]
+    pub attempts: u32,
+    /// The job isn't leased before this time.
+    pub due_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ScheduledJob {
+    pub fn new(
+        channel: impl Into<String>,
+        notification_id: impl Into<String>,
+        payload: serde_json::Value,
+        due_at: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            channel: channel.into(),
+            instance: None,
+            notification_id: notification_id.into(),
+            payload,
+            attempts: 0,
+            due_at,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Send the message with the named instance of the channel.
+    pub fn with_instance(mut self, instance: Option<&str>) -> Self {
+        self.instance = instance.map(ToOwned::to_owned);
+        self
+    }
+}
+
+/// Durable storage for the scheduled jobs, so that they survive restarts.
+///
+/// Workers lease the due jobs, which hides them from other workers until the
+/// lease expires, and complete each job once it has been handled.
+#[async_trait]
+pub trait ScheduleStore: Sync + Send + 'static {
+    /// Add the job to the store.
+    async fn insert(&self, job: ScheduledJob) -> Result<(), Error>;
+
+    /// Get the job, if it hasn't been completed or cancelled.
+    async fn get(&self, id: Uuid) -> Result<Option<ScheduledJob>, Error>;
+
+    /// Remove the job before it is sent. Returns false when the job doesn't
+    /// exist, e.g. because it has already been sent.
+    async fn cancel(&self, id: Uuid) -> Result<bool, Error>;
+
+    /// Lease up to `limit` of the due jobs for the duration, incrementing
+    /// their attempts.
+    async fn lease(&self, limit: usize, lease: Duration) -> Result<Vec<ScheduledJob>, Error>;
+
+    /// Remove the job after it has been handled.
+    async fn complete(&self, id: Uuid) -> Result<(), Error>;
+}
+
+static_assertions::assert_obj_safe!(ScheduleStore);
+
+/// Handle to a scheduled job that can be used to cancel it.
+#[derive(Clone)]
+pub struct ScheduleHandle {
+    id: Uuid,
+    due_at: DateTime<Utc>,
+    store: Arc<dyn ScheduleStore>,
+}
+
+impl ScheduleHandle {
+    pub(crate) fn new(id: Uuid, due_at: DateTime<Utc>, store: Arc<dyn ScheduleStore>) -> Self {
+        Self { id, due_at, store }
+    }
+
+    /// The id of the scheduled job, which can be persisted to cancel the job
+    /// after a restart with [`crate::Notifier::cancel_scheduled`].
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn due_at(&self) -> DateTime<Utc> {
+        self.due_at
+    }
+
+    /// Cancel the job. Returns false when the job has already been sent or
+    /// cancelled.
+    pub async fn cancel(&self) -> Result<bool, Error> {
+        self.store.cancel(self.id).await
+    }
+}
+
+impl std::fmt::Debug for ScheduleHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScheduleHandle")
+            .field("id", &self.id)
+            .field("due_at", &self.due_at)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test_schedule {
+    use super::*;
+    use crate::{
+        channel::Channel, contact::DynamicRecipient, report::Outcome, testing::*, Notification,
+        Priority, QuietHours, Recipient,
+    };
+
+    #[derive(serde::Serialize)]
+    struct TestCriticalNotification {
+        message: String,
+    }
+
+    impl Notification for TestCriticalNotification {
+        type Id = &'static str;
+
+        fn id() -> Self::Id {
+            TestNotification::id()
+        }
+
+        fn priority(&self) -> Priority {
+            Priority::Critical
+        }
+    }
+
+    #[tokio::test]
+    async fn test_notify_defers_during_quiet_hours() {
+        let channel = TestChannel::default();
+
+        let notifier = test_notifier(channel.clone())
+            .configure(|notifier| notifier.set_schedule_store(InMemoryScheduleStore::new()))
+            .build()
+            .unwrap();
+
+        let now = Utc::now();
+        let quiet_hours = QuietHours::new(
+            (now - chrono::Duration::hours(1)).time(),
+            (now + chrono::Duration::hours(1)).time(),
+        );
+
+        let recipient = || {
+            Recipient::new()
+                .with_timezone(chrono_tz::UTC)
+                .with_quiet_hours(quiet_hours)
+                .with_contact(TestContact("Destination (1)".to_string()))
+        };
+
+        let report = notifier
+            .notify(
+                recipient(),
+                TestNotification::new(1, "deferred".to_string()),
+            )
+            .await
+            .unwrap();
+
+        let channel_type = <TestChannel as Channel<&'static str>>::channel_type(&channel);
+
+        match report.get(channel_type) {
+            Some(Outcome::Deferred(handle)) => assert!(handle.due_at() > now),
+            outcome => panic!("expected the notification to be deferred: {outcome:?}"),
+        }
+        assert!(channel.messages.lock().unwrap().is_empty());
+
+        let report = notifier
+            .notify(
+                recipient(),
+                TestCriticalNotification {
+                    message: "critical".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+
+        assert!(report.is_success());
+
+        let messages = channel.messages.lock().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].contents.output, "message = critical");
+    }
+
+    #[tokio::test]
+    async fn test_notify_dynamic_defers_during_quiet_hours() {
+        let channel = TestChannel::default();
+
+        let notifier = test_notifier(channel.clone())
+            .configure(|notifier| notifier.set_schedule_store(InMemoryScheduleStore::new()))
+            .build()
+            .unwrap();
+
+        let now = Utc::now();
+        let time = |hours| {
+            (now + chrono::Duration::hours(hours))
+                .with_timezone(&chrono_tz::Asia::Tokyo)
+                .format("%H:%M:%S")
+                .to_string()
+        };
+
+        let recipient = |priority| -> DynamicRecipient {
+            serde_json::from_value(serde_json::json!({
+                "timezone": "Asia/Tokyo",
+                "quiet_hours": { "start": time(-1), "end": time(1) },
+                "priority": priority,
+                "contacts": { "test": "Destination (1)" }
+            }))
+            .unwrap()
+        };
+
+        let report = notifier
+            .notify_dynamic(
+                "test_notification",
+                &serde_json::json!({ "message": "deferred" }),
+                recipient("normal"),
+            )
+            .await
+            .unwrap();
+
+        let channel_type = <TestChannel as Channel<&'static str>>::channel_type(&channel);
+
+        assert!(report.get(channel_type).unwrap().is_deferred());
+        assert!(channel.messages.lock().unwrap().is_empty());
+
+        let report = notifier
+            .notify_dynamic(
+                "test_notification",
+                &serde_json::json!({ "message": "critical" }),
+                recipient("critical"),
+            )
+            .await
+            .unwrap();
+
+        assert!(report.get(channel_type).unwrap().is_sent());
+        assert_eq!(channel.messages.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_notify_during_quiet_hours_without_schedule_store() {
+        let channel = TestChannel::default();
+
+        let notifier = test_notifier(channel.clone()).build().unwrap();
+
+        let now = Utc::now();
+        let recipient = Recipient::new()
+            .with_quiet_hours(QuietHours::new(
+                (now - chrono::Duration::hours(1)).time(),
+                (now + chrono::Duration::hours(1)).time(),
+            ))
+            .with_contact(TestContact("Destination (1)".to_string()));
+
+        let report = notifier
+            .notify(recipient, TestNotification::new(1, "quiet".to_string()))
+            .await
+            .unwrap();
+
+        assert!(report.is_success());
+        assert_eq!(channel.messages.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,78 @@
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use uuid::Uuid;
+
+use super::{ScheduleStore, ScheduledJob};
+use crate::Error;
+
+/// Schedule store that keeps the jobs in memory, they are lost when the
+/// process exits.
+#[derive(Default)]
+pub struct InMemoryScheduleStore {
+    jobs: Mutex<HashMap<Uuid, ScheduledJob>>,
+}
+
+impl InMemoryScheduleStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of jobs that haven't been completed or cancelled.
+    pub fn len(&self) -> usize {
+        self.jobs.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl ScheduleStore for InMemoryScheduleStore {
+    async fn insert(&self, job: ScheduledJob) -> Result<(), Error> {
+        self.jobs.lock().unwrap().insert(job.id, job);
+        Ok(())
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<ScheduledJob>, Error> {
+        Ok(self.jobs.lock().unwrap().get(&id).cloned())
+    }
+
+    async fn cancel(&self, id: Uuid) -> Result<bool, Error> {
+        Ok(self.jobs.lock().unwrap().remove(&id).is_some())
+    }
+
+    async fn lease(&self, limit: usize, lease: Duration) -> Result<Vec<ScheduledJob>, Error> {
+        let now = Utc::now();
+        let lease = chrono::Duration::from_std(lease).map_err(|e| Error::Store(e.into()))?;
+
+        let mut jobs = self.jobs.lock().unwrap();
+
+        let mut due = jobs
+            .values_mut()
+            .filter(|job| job.due_at <= now)
+            .collect::<Vec<_>>();
+
+        due.sort_by_key(|job| job.due_at);
+
+        let leased = due
+            .into_iter()
+            .take(limit)
+            .map(|job| {
+                job.attempts += 1;
+                let leased = job.clone();
+                job.due_at = now + lease;
+                leased
+            })
+            .collect();
+
+        Ok(leased)
+    }
+
+    async fn complete(&self, id: Uuid) -> Result<(), Error> {
+        self.jobs.lock().unwrap().remove(&id);
+        Ok(())
+    }
+}
@@ -0,0 +1,214 @@
+use std::{path::Path, sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use uuid::Uuid;
+
+use super::{ScheduleStore, ScheduledJob};
+use crate::{
+    sqlite::{from_millis, parse_json, parse_uuid, store_error, to_millis},
+    Error,
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS notifier_schedule (
+    id TEXT PRIMARY KEY NOT NULL,
+    channel TEXT NOT NULL,
+    notification_id TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    due_at INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    instance TEXT,
+    attempts INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS notifier_schedule_due ON notifier_schedule (due_at);
+";
+
+const COLUMNS: &str =
+    "id, channel, notification_id, payload, due_at, created_at, instance, attempts";
+
+/// Schedule store that persists the jobs in a SQLite database. Queries run on
+/// the calling task, which is fine for SQLite's short lived writes.
+pub struct SqliteScheduleStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteScheduleStore {
+    /// Open the database at the path, creating the schedule's table if it
+    /// doesn't exist.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let conn = Connection::open(path).map_err(store_error)?;
+        Self::from_connection(conn)
+    }
+
+    /// Open an in-memory database, mostly useful for tests.
+    pub fn open_in_memory() -> Result<Self, Error> {
+        let conn = Connection::open_in_memory().map_err(store_error)?;
+        Self::from_connection(conn)
+    }
+
+    /// Use the connection, creating the schedule's table if it doesn't exist.
+    pub fn from_connection(conn: Connection) -> Result<Self, Error> {
+        conn.execute_batch(SCHEMA).map_err(store_error)?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+}
+
+fn from_row(row: &Row) -> rusqlite::Result<ScheduledJob> {
+    let id: String = row.get(0)?;
+    let payload: String = row.get(3)?;
+
+    Ok(ScheduledJob {
+        id: parse_uuid(0, &id)?,
+        channel: row.get(1)?,
+        instance: row.get(6)?,
+        attempts: row.get(7)?,
+        notification_id: row.get(2)?,
+        payload: parse_json(3, &payload)?,
+        due_at: from_millis(row.get(4)?)?,
+        created_at: from_millis(row.get(5)?)?,
+    })
+}
+
+#[async_trait]
+impl ScheduleStore for SqliteScheduleStore {
+    async fn insert(&self, job: ScheduledJob) -> Result<(), Error> {
+        let payload = serde_json::to_string(&job.payload).map_err(Error::Serde)?;
+
+        self.conn
+            .lock()
+            .unwrap()
+            .execute(
+                &format!(
+                    "INSERT INTO notifier_schedule ({COLUMNS})
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"
+                ),
+                params![
+                    job.id.to_string(),
+                    job.channel,
+                    job.notification_id,
+                    payload,
+                    to_millis(job.due_at),
+                    to_millis(job.created_at),
+                    job.instance,
+                    job.attempts,
+                ],
+            )
+            .map_err(store_error)?;
+
+        Ok(())
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<ScheduledJob>, Error> {
+        self.conn
+            .lock()
+            .unwrap()
+            .query_row(
+                &format!("SELECT {COLUMNS} FROM notifier_schedule WHERE id = ?1"),
+                params![id.to_string()],
+                from_row,
+            )
+            .optional()
+            .map_err(store_error)
+    }
+
+    async fn cancel(&self, id: Uuid) -> Result<bool, Error> {
+        let deleted = self
+            .conn
+            .lock()
+            .unwrap()
+            .execute(
+                "DELETE FROM notifier_schedule WHERE id = ?1",
+                params![id.to_string()],
+            )
+            .map_err(store_error)?;
+
+        Ok(deleted > 0)
+    }
+
+    async fn lease(&self, limit: usize, lease: Duration) -> Result<Vec<ScheduledJob>, Error> {
+        let now = Utc::now();
+        let lease = chrono::Duration::from_std(lease).map_err(|e| Error::Store(e.into()))?;
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction().map_err(store_error)?;
+
+        let mut jobs = {
+            let mut stmt = tx
+                .prepare(&format!(
+                    "SELECT {COLUMNS} FROM notifier_schedule
+                    WHERE due_at <= ?1
+                    ORDER BY due_at
+                    LIMIT ?2"
+                ))
+                .map_err(store_error)?;
+
+            let rows = stmt
+                .query_map(params![to_millis(now), limit as i64], from_row)
+                .map_err(store_error)?;
+
+            rows.collect::<Result<Vec<_>, _>>().map_err(store_error)?
+        };
+
+        for job in jobs.iter_mut() {
+            job.attempts += 1;
+
+            tx.execute(
+                "UPDATE notifier_schedule SET due_at = ?2, attempts = ?3 WHERE id = ?1",
+                params![job.id.to_string(), to_millis(now + lease), job.attempts],
+            )
+            .map_err(store_error)?;
+        }
+
+        tx.commit().map_err(store_error)?;
+
+        Ok(jobs)
+    }
+
+    async fn complete(&self, id: Uuid) -> Result<(), Error> {
+        self.cancel(id).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_sqlite_schedule_store {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_lease_due_jobs() {
+        let store = SqliteScheduleStore::open_in_memory().unwrap();
+
+        let due = ScheduledJob::new("channel", "notification", serde_json::json!({}), Utc::now());
+        let later = ScheduledJob::new(
+            "channel",
+            "notification",
+            serde_json::json!({}),
+            Utc::now() + chrono::Duration::hours(1),
+        );
+
+        store.insert(due.clone()).await.unwrap();
+        store.insert(later.clone()).await.unwrap();
+
+        let leased = store.lease(10, Duration::from_secs(30)).await.unwrap();
+        assert_eq!(leased.len(), 1);
+        assert_eq!(leased[0].id, due.id);
+
+        assert!(store
+            .lease(10, Duration::from_secs(30))
+            .await
+            .unwrap()
+            .is_empty());
+
+        store.complete(due.id).await.unwrap();
+        assert!(store.get(due.id).await.unwrap().is_none());
+
+        assert!(store.cancel(later.id).await.unwrap());
+        assert!(!store.cancel(later.id).await.unwrap());
+    }
+}
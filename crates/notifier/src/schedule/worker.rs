@@ -0,0 +1,371 @@
+use std::{future::Future, time::Duration};
+
+use super::ScheduledJob;
+use crate::{outbox::OutboxMessage, Error, Id, Notifier};
+
+#[derive(Debug, Clone)]
+pub struct ScheduleWorkerOptions {
+    /// The maximum number of jobs leased at once.
+    pub batch_size: usize,
+    /// How long a leased job is hidden from other workers. Jobs that fail
+    /// with a transient error are retried once their lease expires.
+    pub lease: Duration,
+    /// How long to wait before polling the store when no jobs were due.
+    pub poll_interval: Duration,
+    /// The number of times a job that fails with a transient error is sent
+    /// before it is dropped, when the notifier doesn't have an outbox.
+    pub max_attempts: u32,
+}
+
+impl Default for ScheduleWorkerOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: 10,
+            lease: Duration::from_secs(60),
+            poll_interval: Duration::from_secs(1),
+            max_attempts: 5,
+        }
+    }
+}
+
+/// Sends the notifier's scheduled jobs once they are due.
+///
+/// When the notifier has an outbox the due jobs are moved into it, so that
+/// the outbox worker handles retries and dead lettering. Otherwise the jobs
+/// are sent straight away and only retried on transient errors, until they
+/// run out of attempts.
+pub struct ScheduleWorker<'a, I: Id> {
+    notifier: &'a Notifier<I>,
+    options: ScheduleWorkerOptions,
+}
+
+impl<'a, I: Id> ScheduleWorker<'a, I> {
+    pub fn new(notifier: &'a Notifier<I>, options: ScheduleWorkerOptions) -> Self {
+        Self { notifier, options }
+    }
+
+    /// Lease a batch of due jobs and handle them, returning the number of
+    /// jobs that were leased. A job that can't be handled is logged and left
+    /// to be leased again, it doesn't stop the rest of the batch.
+    pub async fn run_once(&self) -> Result<usize, Error> {
+        let store = self
+            .notifier
+            .schedule_store()
+            .ok_or(Error::NoScheduleStore)?;
+
+        let jobs = store
+            .lease(self.options.batch_size, self.options.lease)
+            .await?;
+        let leased = jobs.len();
+
+        for job in jobs {
+            let id = job.id;
+
+            if let Err(e) = self.handle(job).await {
+                tracing::warn!(job_id = %id, error = %e, "failed to handle scheduled job");
+            }
+        }
+
+        Ok(leased)
+    }
+
+    /// Keep handling the due jobs until the shutdown future completes.
+    /// Returns early if the store fails to lease the jobs.
+    pub async fn run_until<F: Future<Output = ()>>(&self, shutdown: F) -> Result<(), Error> {
+        tokio::pin!(shutdown);
+
+        loop {
+            let leased = self.run_once().await?;
+
+            // keep going straight away while there are more jobs due
+            let wait = if leased < self.options.batch_size {
+                self.options.poll_interval
+            } else {
+                Duration::ZERO
+            };
+
+            tokio::select! {
+                biased;
+                _ = &mut shutdown => return Ok(()),
+                _ = tokio::time::sleep(wait) => {}
+            }
+        }
+    }
+
+    async fn handle(&self, job: ScheduledJob) -> Result<(), Error> {
+        let store = self
+            .notifier
+            .schedule_store()
+            .ok_or(Error::NoScheduleStore)?;
+
+        if let Some(outbox) = self.notifier.outbox() {
+            let mut message = OutboxMessage::new(job.channel, job.notification_id, job.payload)
+                .with_instance(job.instance.as_deref());
+            message.id = job.id;
+
+            // the outbox ignores the message if the job was already moved, e.g.
+            // before a crash stopped it from being completed
+            outbox.enqueue(message).await?;
+
+            return store.complete(job.id).await;
+        }
+
+        match self
+            .notifier
+            .send_serialized_message(
+                &job.channel,
+                job.instance.as_deref(),
+                &job.notification_id,
+                job.payload,
+            )
+            .await
+        {
+            Ok(_) => store.complete(job.id).await,
+            // leave the job to be leased again once the lease expires
+            Err(e) if e.is_transient() && job.attempts < self.options.max_attempts => Ok(()),
+            Err(e) => {
+                tracing::warn!(
+                    job_id = %job.id,
+                    attempts = job.attempts,
+                    error = %e,
+                    "dropping scheduled job that failed"
+                );
+                store.complete(job.id).await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_schedule_worker {
+    use chrono::Utc;
+
+    use async_trait::async_trait;
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::{
+        outbox::InMemoryOutbox,
+        provider::ErrorKind,
+        schedule::InMemoryScheduleStore,
+        template::{engine::RenderContext, RegisteredTemplate, TemplateId, TemplateService},
+        testing::*,
+        Channel, DeliveryReceipt, ProviderError,
+    };
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct FlakyContact(String);
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct FlakyMessage(String);
+
+    struct FlakyTemplate(TemplateId);
+
+    impl RegisteredTemplate for FlakyTemplate {
+        fn template_ids(&self) -> Vec<TemplateId> {
+            vec![self.0]
+        }
+    }
+
+    /// Channel whose sends always fail with a transient error.
+    struct FlakyChannel;
+
+    #[async_trait]
+    impl Channel<&'static str> for FlakyChannel {
+        const KEY: &'static str = "flaky";
+
+        type Contact = FlakyContact;
+        type Message = FlakyMessage;
+        type RenderedTemplate = String;
+        type UserTemplate = &'static str;
+
+        fn create_message(&self, _: FlakyContact, contents: String) -> Result<FlakyMessage, Error> {
+            Ok(FlakyMessage(contents))
+        }
+
+        async fn send(&self, _: FlakyMessage) -> Result<DeliveryReceipt, Error> {
+            Err(ProviderError::Send {
+                kind: ErrorKind::Transient,
+                channel_id: "flaky",
+                provider_id: "flaky",
+                source: anyhow::Error::msg("the flaky channel is down"),
+                context: None,
+            }
+            .into())
+        }
+
+        fn register_template(
+            &self,
+            notification_id: &'static str,
+            source: &'static str,
+            template_service: &mut TemplateService<&'static str>,
+        ) -> Result<(), Error> {
+            let template_id = template_service.engine_mut().register(source)?;
+            template_service.register_template(
+                notification_id,
+                Channel::channel_type(self),
+                FlakyTemplate(template_id),
+            );
+            Ok(())
+        }
+
+        fn render_template(
+            &self,
+            notification_id: &&'static str,
+            context: &RenderContext,
+            template_service: &TemplateService<&'static str>,
+        ) -> Result<String, Error> {
+            let template = template_service
+                .get_template::<FlakyTemplate>(notification_id, Channel::channel_type(self))?;
+
+            Ok(template_service.render_template(template.0, context)?)
+        }
+    }
+
+    fn create_notifier(channel: TestChannel) -> Notifier<&'static str> {
+        let mut notifier = Notifier::<&'static str>::default();
+
+        notifier.register_channel(channel).unwrap();
+        notifier.set_schedule_store(InMemoryScheduleStore::new());
+
+        notifier
+            .register_notification::<TestNotification, TestTemplate>(TestTemplate(
+                "message = {{message}}",
+            ))
+            .unwrap();
+
+        notifier
+    }
+
+    #[tokio::test]
+    async fn test_sends_due_jobs() {
+        let channel = TestChannel::default();
+        let notifier = create_notifier(channel.clone());
+
+        let now = Utc::now();
+
+        notifier
+            .schedule(
+                TestNotification::new(1, "due".to_string()),
+                TestContact("Destination (1)".to_string()),
+                now,
+            )
+            .await
+            .unwrap();
+        notifier
+            .schedule(
+                TestNotification::new(2, "later".to_string()),
+                TestContact("Destination (2)".to_string()),
+                now + chrono::Duration::hours(1),
+            )
+            .await
+            .unwrap();
+
+        let worker = ScheduleWorker::new(&notifier, ScheduleWorkerOptions::default());
+
+        assert_eq!(worker.run_once().await.unwrap(), 1);
+
+        let messages = channel.messages.lock().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].contents.output, "message = due");
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_jobs_are_not_sent() {
+        let channel = TestChannel::default();
+        let notifier = create_notifier(channel.clone());
+
+        let handle = notifier
+            .schedule(
+                TestNotification::new(1, "cancelled".to_string()),
+                TestContact("Destination (1)".to_string()),
+                Utc::now(),
+            )
+            .await
+            .unwrap();
+
+        assert!(handle.cancel().await.unwrap());
+        assert!(!notifier.cancel_scheduled(handle.id()).await.unwrap());
+
+        let worker = ScheduleWorker::new(&notifier, ScheduleWorkerOptions::default());
+
+        assert_eq!(worker.run_once().await.unwrap(), 0);
+        assert!(channel.messages.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_drops_transient_failures_after_max_attempts() {
+        let mut notifier = Notifier::<&'static str>::new();
+        notifier.register_channel(FlakyChannel).unwrap();
+        notifier.set_schedule_store(InMemoryScheduleStore::new());
+        notifier
+            .register_notification::<TestNotification, &'static str>("message = {{message}}")
+            .unwrap();
+
+        notifier
+            .schedule(
+                TestNotification::new(1, "flaky".to_string()),
+                FlakyContact("Destination (1)".to_string()),
+                Utc::now(),
+            )
+            .await
+            .unwrap();
+
+        let worker = ScheduleWorker::new(
+            &notifier,
+            ScheduleWorkerOptions {
+                lease: Duration::ZERO,
+                max_attempts: 2,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(worker.run_once().await.unwrap(), 1);
+        assert_eq!(worker.run_once().await.unwrap(), 1);
+        assert_eq!(worker.run_once().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_moving_a_job_to_the_outbox_twice_is_ignored() {
+        let channel = TestChannel::default();
+        let mut notifier = create_notifier(channel.clone());
+        notifier.set_outbox(InMemoryOutbox::new());
+
+        notifier
+            .schedule(
+                TestNotification::new(1, "due".to_string()),
+                TestContact("Destination (1)".to_string()),
+                Utc::now(),
+            )
+            .await
+            .unwrap();
+
+        let store = notifier.schedule_store().unwrap();
+        let job = store
+            .lease(10, Duration::ZERO)
+            .await
+            .unwrap()
+            .pop()
+            .unwrap();
+
+        // the job was moved to the outbox but not completed before a crash
+        let message = OutboxMessage::new(job.channel, job.notification_id, job.payload);
+        notifier
+            .outbox()
+            .unwrap()
+            .enqueue(OutboxMessage {
+                id: job.id,
+                ..message
+            })
+            .await
+            .unwrap();
+
+        let worker = ScheduleWorker::new(&notifier, ScheduleWorkerOptions::default());
+
+        assert_eq!(worker.run_once().await.unwrap(), 1);
+        assert!(store.get(job.id).await.unwrap().is_none());
+
+        let outbox = notifier.outbox().unwrap();
+        assert_eq!(outbox.lease(10, Duration::ZERO).await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,33 @@
+//! Helpers shared by the SQLite backed stores.
+
+use chrono::{DateTime, TimeZone, Utc};
+use rusqlite::types::Type;
+use serde::de::DeserializeOwned;
+use uuid::Uuid;
+
+use crate::Error;
+
+pub(crate) fn store_error(e: rusqlite::Error) -> Error {
+    Error::Store(e.into())
+}
+
+/// Timestamps are stored as milliseconds so that they can be compared.
+pub(crate) fn to_millis(time: DateTime<Utc>) -> i64 {
+    time.timestamp_millis()
+}
+
+pub(crate) fn from_millis(millis: i64) -> rusqlite::Result<DateTime<Utc>> {
+    Utc.timestamp_millis_opt(millis)
+        .single()
+        .ok_or(rusqlite::Error::IntegralValueOutOfRange(0, millis))
+}
+
+pub(crate) fn parse_uuid(index: usize, id: &str) -> rusqlite::Result<Uuid> {
+    Uuid::parse_str(id)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, Type::Text, e.into()))
+}
+
+pub(crate) fn parse_json<T: DeserializeOwned>(index: usize, json: &str) -> rusqlite::Result<T> {
+    serde_json::from_str(json)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, Type::Text, e.into()))
+}
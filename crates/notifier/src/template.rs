@@ -14,6 +14,13 @@ pub use service::TemplateService;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct TemplateId(Uuid);
 
+/// A channel's template once it's registered with the [`TemplateService`].
+pub trait RegisteredTemplate: std::any::Any + Send + Sync {
+    /// The ids of the templates it registered with the engine, which are
+    /// removed from the engine when the template is replaced or removed.
+    fn template_ids(&self) -> Vec<TemplateId>;
+}
+
 impl Default for TemplateId {
     fn default() -> Self {
         Self(Uuid::new_v4())
@@ -50,6 +50,20 @@ impl TemplateEngine {
         Ok(id)
     }
 
+    /// Remove the template, returning false if it wasn't registered.
+    pub fn remove(&mut self, id: TemplateId) -> bool {
+        self.templates.remove(&id).is_some()
+    }
+
+    /// The number of registered templates.
+    pub fn len(&self) -> usize {
+        self.templates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty()
+    }
+
     /// Render the template to a string using the context's data
     pub fn render(&self, id: TemplateId, ctx: &RenderContext) -> Result<String, TemplateError> {
         let template = self
@@ -78,4 +92,18 @@ mod test_template_engine {
 
         assert_eq!(&output, "Hello, World!");
     }
+
+    #[test]
+    fn test_remove() {
+        let mut engine = TemplateEngine::new();
+        let id = engine.register("Hello, {{ name }}!").unwrap();
+
+        assert!(engine.remove(id));
+        assert!(!engine.remove(id));
+        assert!(engine.is_empty());
+        assert!(matches!(
+            engine.render(id, &RenderContext::new(liquid::object!({}))),
+            Err(TemplateError::UnknownTemplate(_))
+        ));
+    }
 }
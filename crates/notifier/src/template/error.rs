@@ -21,7 +21,7 @@ pub enum Error {
     #[error("A template with this ID doesn't exist engine")]
     UnknownTemplate(TemplateId),
 
-    #[error("A template hasn't been registered for this channel and notification")]
+    #[error("A template hasn't been registered for the {channel_type} channel and notification {notification_id}")]
     NotFound {
         channel_type: ChannelType,
         notification_id: String,
@@ -8,6 +8,8 @@ pub enum MarkupType {
 
 pub enum Markup<'a> {
     Mjml(&'a str),
+    /// HTML that is used as is, e.g. MJML compiled while building.
+    Html(&'a str),
 }
 
 impl<'a> Markup<'a> {
@@ -24,6 +26,7 @@ impl<'a> Markup<'a> {
                     source: anyhow::Error::msg(source.to_string()),
                     ty: MarkupType::Mjml,
                 })?,
+            Self::Html(html) => (*html).to_owned(),
         };
 
         Ok(output)
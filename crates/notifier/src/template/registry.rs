@@ -1,41 +1,135 @@
-use std::{
-    any::Any,
-    collections::{HashMap, HashSet},
-};
+use std::{any::Any, collections::HashMap};
 
+use super::TemplateId;
 use crate::{channel::ChannelType, Id};
 
-#[derive(Default)]
+struct Entry {
+    template: Box<dyn Any + Send + Sync>,
+    /// The ids of the template's parts in the engine.
+    template_ids: Vec<TemplateId>,
+}
+
+type Templates = HashMap<ChannelType, Entry>;
+
 pub struct TemplateRegistry<I: Id> {
-    notifications: HashMap<I, HashSet<ChannelType>>,
-    templates: HashMap<(I, ChannelType), Box<dyn Any>>,
+    templates: HashMap<I, Templates>,
+}
+
+impl<I: Id> Default for TemplateRegistry<I> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<I: Id> TemplateRegistry<I> {
     pub fn new() -> Self {
         Self {
-            notifications: HashMap::new(),
             templates: HashMap::new(),
         }
     }
 
+    /// Register the template, returning the engine ids of the template it
+    /// replaced.
     pub fn register(
         &mut self,
         notification_id: I,
         channel_type: ChannelType,
-        template: Box<dyn Any>,
-    ) {
-        let entry = self.notifications.entry(notification_id).or_default();
-        entry.insert(channel_type);
+        template: Box<dyn Any + Send + Sync>,
+        template_ids: Vec<TemplateId>,
+    ) -> Option<Vec<TemplateId>> {
+        self.templates
+            .entry(notification_id)
+            .or_default()
+            .insert(
+                channel_type,
+                Entry {
+                    template,
+                    template_ids,
+                },
+            )
+            .map(|entry| entry.template_ids)
+    }
+
+    /// Find the registered notification id that displays as the string.
+    pub fn find_id(&self, notification_id: &str) -> Option<I> {
         self.templates
-            .insert((notification_id, channel_type), template);
+            .keys()
+            .find(|id| id.to_string() == notification_id)
+            .cloned()
+    }
+
+    pub fn contains(&self, notification_id: &I, channel_type: ChannelType) -> bool {
+        self.get_template(notification_id, channel_type).is_some()
+    }
+
+    /// Check if a template has been registered for the notification on any
+    /// channel.
+    pub fn contains_notification(&self, notification_id: &I) -> bool {
+        self.templates.contains_key(notification_id)
+    }
+
+    /// The channels that have a template for the notification.
+    pub fn channel_types(&self, notification_id: &I) -> Vec<ChannelType> {
+        self.templates
+            .get(notification_id)
+            .map(|templates| templates.keys().copied().collect())
+            .unwrap_or_default()
     }
 
     pub fn get_template(
         &self,
-        notification_id: I,
+        notification_id: &I,
         channel_type: ChannelType,
-    ) -> Option<&Box<dyn Any>> {
-        self.templates.get(&(notification_id, channel_type))
+    ) -> Option<&(dyn Any + Send + Sync)> {
+        self.templates
+            .get(notification_id)?
+            .get(&channel_type)
+            .map(|entry| entry.template.as_ref())
+    }
+
+    /// Remove the notification's template for the channel, returning its
+    /// engine ids or `None` if it wasn't registered.
+    pub fn remove(
+        &mut self,
+        notification_id: &I,
+        channel_type: ChannelType,
+    ) -> Option<Vec<TemplateId>> {
+        let templates = self.templates.get_mut(notification_id)?;
+
+        let removed = templates.remove(&channel_type);
+
+        if templates.is_empty() {
+            self.templates.remove(notification_id);
+        }
+
+        removed.map(|entry| entry.template_ids)
+    }
+
+    /// Remove the notification's templates for every channel, returning their
+    /// engine ids or `None` if none were registered.
+    pub fn remove_notification(&mut self, notification_id: &I) -> Option<Vec<TemplateId>> {
+        let templates = self.templates.remove(notification_id)?;
+
+        Some(
+            templates
+                .into_values()
+                .flat_map(|entry| entry.template_ids)
+                .collect(),
+        )
+    }
+
+    /// Remove the templates of every notification for the channel, returning
+    /// their engine ids.
+    pub fn remove_channel(&mut self, channel_type: ChannelType) -> Vec<TemplateId> {
+        let mut removed = Vec::new();
+
+        self.templates.retain(|_, templates| {
+            if let Some(entry) = templates.remove(&channel_type) {
+                removed.extend(entry.template_ids);
+            }
+            !templates.is_empty()
+        });
+
+        removed
     }
 }
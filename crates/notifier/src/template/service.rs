@@ -1,17 +1,28 @@
-use std::any::{Any, TypeId};
+use std::{
+    any::{Any, TypeId},
+    time::Instant,
+};
+
+use tracing::field;
 
 use super::{
     engine::{RenderContext, TemplateEngine},
     registry::TemplateRegistry,
-    TemplateError, TemplateId,
+    RegisteredTemplate, TemplateError, TemplateId,
 };
 use crate::{channel::ChannelType, Error, Id};
 
-#[derive(Default)]
 pub struct TemplateService<I: Id> {
     engine: TemplateEngine,
     registry: TemplateRegistry<I>,
 }
+
+impl<I: Id> Default for TemplateService<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<I: Id> TemplateService<I> {
     pub fn new() -> Self {
         Self {
@@ -20,24 +31,86 @@ impl<I: Id> TemplateService<I> {
         }
     }
 
-    /// Register the template with the service for the given channel and
-    /// notification. The template is `Box<dyn Any>`, the channel will
-    /// downcast this to get the concrete type.
-    pub fn register_template(
+    /// Register the channel's template for the notification, releasing the
+    /// engine templates of the one it replaces. Channels get it back with
+    /// [`TemplateService::get_template`].
+    pub fn register_template<T: RegisteredTemplate>(
         &mut self,
         notification_id: I,
         channel_type: ChannelType,
-        template: Box<dyn Any>,
+        template: T,
     ) {
+        let template_ids = template.template_ids();
+
+        if let Some(replaced) = self.registry.register(
+            notification_id,
+            channel_type,
+            Box::new(template),
+            template_ids,
+        ) {
+            self.release(replaced);
+        }
+    }
+
+    /// Check if a template has been registered for the channel and
+    /// notification.
+    pub fn has_template(&self, notification_id: &I, channel_type: ChannelType) -> bool {
+        self.registry.contains(notification_id, channel_type)
+    }
+
+    /// Check if a template has been registered for the notification on any
+    /// channel.
+    pub fn has_notification(&self, notification_id: &I) -> bool {
+        self.registry.contains_notification(notification_id)
+    }
+
+    /// The channels that have a template for the notification.
+    pub fn notification_channels(&self, notification_id: &I) -> Vec<ChannelType> {
+        self.registry.channel_types(notification_id)
+    }
+
+    /// Remove the notification's template for the channel, returning false
+    /// if it wasn't registered.
+    pub fn remove_template(&mut self, notification_id: &I, channel_type: ChannelType) -> bool {
+        self.registry
+            .remove(notification_id, channel_type)
+            .map(|removed| self.release(removed))
+            .is_some()
+    }
+
+    /// Remove the notification's templates for every channel, returning false
+    /// if none were registered.
+    pub fn remove_notification(&mut self, notification_id: &I) -> bool {
         self.registry
-            .register(notification_id, channel_type, template)
+            .remove_notification(notification_id)
+            .map(|removed| self.release(removed))
+            .is_some()
+    }
+
+    /// Remove the templates of every notification for the channel.
+    pub fn remove_channel(&mut self, channel_type: ChannelType) {
+        let removed = self.registry.remove_channel(channel_type);
+        self.release(removed);
+    }
+
+    /// Remove the templates from the engine.
+    fn release(&mut self, template_ids: Vec<TemplateId>) {
+        for template_id in template_ids {
+            self.engine.remove(template_id);
+        }
+    }
+
+    /// Find the id of a registered notification from its string form, e.g. the
+    /// id of a persisted message.
+    pub fn find_notification_id(&self, notification_id: &str) -> Option<I> {
+        self.registry.find_id(notification_id)
     }
 
     /// Get a reference to the template registered for the channel and
     /// notification.
     pub fn get_template<T: Any>(
         &self,
-        notification_id: I,
+        notification_id: &I,
         channel_type: ChannelType,
     ) -> Result<&T, Error> {
         let template = self
@@ -50,7 +123,7 @@ impl<I: Id> TemplateService<I> {
 
         let template = template.downcast_ref::<T>().ok_or(Error::Downcast {
             context: Some("Failed to downcast the template into T"),
-            found: (&*template).type_id(),
+            found: template.type_id(),
             expected: TypeId::of::<T>(),
         })?;
 
@@ -71,6 +144,23 @@ impl<I: Id> TemplateService<I> {
         template_id: TemplateId,
         context: &RenderContext,
     ) -> Result<String, TemplateError> {
-        self.engine().render(template_id, context)
+        let span = tracing::debug_span!(
+            "render_template",
+            template_id = ?template_id,
+            outcome = field::Empty,
+            latency_ms = field::Empty,
+        );
+        let _entered = span.enter();
+
+        let start = Instant::now();
+        let result = self.engine().render(template_id, context);
+
+        span.record("latency_ms", start.elapsed().as_millis() as u64);
+        span.record(
+            "outcome",
+            if result.is_ok() { "rendered" } else { "failed" },
+        );
+
+        result
     }
 }
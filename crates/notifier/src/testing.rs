@@ -0,0 +1,10 @@
+//! Helpers for testing applications that send notifications, enabled with the
+//! `testing` feature.
+
+#[cfg(test)]
+mod fixtures;
+mod provider;
+
+#[cfg(test)]
+pub(crate) use fixtures::*;
+pub use provider::{RecordingProvider, ScriptedProvider};
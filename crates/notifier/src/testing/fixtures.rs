@@ -0,0 +1,224 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    provider::ErrorKind,
+    receipt::DeliveryReceipt,
+    template::{RegisteredTemplate, TemplateId},
+    Channel, Error, Id, Notification, Notifier, NotifierBuilder, ProviderError,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TestContact(pub String);
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TestMessageContents {
+    pub output: String,
+    pub notification_id: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TestMessage {
+    pub contact: TestContact,
+    pub contents: TestMessageContents,
+}
+
+#[derive(Default)]
+pub struct TestChannel {
+    pub messages: Arc<Mutex<Vec<TestMessage>>>,
+}
+
+impl Clone for TestChannel {
+    fn clone(&self) -> Self {
+        Self {
+            messages: self.messages.clone(),
+        }
+    }
+}
+
+pub struct TestTemplate(pub &'static str);
+pub struct TestRegisteredTemplate(TemplateId);
+
+impl RegisteredTemplate for TestRegisteredTemplate {
+    fn template_ids(&self) -> Vec<TemplateId> {
+        vec![self.0]
+    }
+}
+
+#[async_trait]
+impl<I: Id> Channel<I> for TestChannel {
+    const KEY: &'static str = "test";
+
+    type Contact = TestContact;
+    type Message = TestMessage;
+    type RenderedTemplate = TestMessageContents;
+    type UserTemplate = TestTemplate;
+
+    /// Create a message that has the contact as the recipient
+    fn create_message(
+        &self,
+        contact: TestContact,
+        contents: TestMessageContents,
+    ) -> Result<Self::Message, Error> {
+        let message = TestMessage { contact, contents };
+        Ok(message)
+    }
+
+    async fn send(&self, message: Self::Message) -> Result<DeliveryReceipt, crate::Error> {
+        let mut messages = self.messages.lock().unwrap();
+        messages.push(message);
+
+        let receipt =
+            DeliveryReceipt::new("test").with_provider_message_id(messages.len().to_string());
+
+        Ok(receipt)
+    }
+
+    fn register_template(
+        &self,
+        notification_id: I,
+        source: Self::UserTemplate,
+        template_service: &mut crate::template::TemplateService<I>,
+    ) -> Result<(), Error> {
+        let template_id = template_service.engine_mut().register(source.0)?;
+        let template = TestRegisteredTemplate(template_id);
+
+        let channel_type = <Self as Channel<I>>::channel_type(self);
+
+        template_service.register_template(notification_id, channel_type, template);
+
+        Ok(())
+    }
+
+    fn render_template(
+        &self,
+        notification_id: &I,
+        context: &crate::template::engine::RenderContext,
+        template_service: &crate::template::TemplateService<I>,
+    ) -> Result<Self::RenderedTemplate, Error> {
+        let channel_type = <Self as Channel<I>>::channel_type(self);
+
+        let template = template_service
+            .get_template::<TestRegisteredTemplate>(notification_id, channel_type)?;
+
+        let output = template_service.render_template(template.0, context)?;
+
+        let contents = TestMessageContents {
+            output,
+            notification_id: notification_id.to_string(),
+        };
+
+        Ok(contents)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FailingContact(pub String);
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FailingMessage {
+    pub contact: FailingContact,
+    pub contents: String,
+}
+
+pub struct FailingTemplate(pub &'static str);
+
+/// Channel that always fails to send its messages.
+pub struct FailingChannel;
+
+#[async_trait]
+impl<I: Id> Channel<I> for FailingChannel {
+    const KEY: &'static str = "failing";
+
+    type Contact = FailingContact;
+    type Message = FailingMessage;
+    type RenderedTemplate = String;
+    type UserTemplate = FailingTemplate;
+
+    fn create_message(
+        &self,
+        contact: FailingContact,
+        contents: String,
+    ) -> Result<Self::Message, Error> {
+        Ok(FailingMessage { contact, contents })
+    }
+
+    async fn send(&self, _message: Self::Message) -> Result<DeliveryReceipt, Error> {
+        Err(ProviderError::Send {
+            kind: ErrorKind::Permanent,
+            channel_id: "failing",
+            provider_id: "failing",
+            source: anyhow::Error::msg("the failing channel always fails"),
+            context: None,
+        }
+        .into())
+    }
+
+    fn register_template(
+        &self,
+        notification_id: I,
+        source: Self::UserTemplate,
+        template_service: &mut crate::template::TemplateService<I>,
+    ) -> Result<(), Error> {
+        let template_id = template_service.engine_mut().register(source.0)?;
+        let channel_type = <Self as Channel<I>>::channel_type(self);
+
+        template_service.register_template(
+            notification_id,
+            channel_type,
+            TestRegisteredTemplate(template_id),
+        );
+
+        Ok(())
+    }
+
+    fn render_template(
+        &self,
+        notification_id: &I,
+        context: &crate::template::engine::RenderContext,
+        template_service: &crate::template::TemplateService<I>,
+    ) -> Result<Self::RenderedTemplate, Error> {
+        let channel_type = <Self as Channel<I>>::channel_type(self);
+
+        let template = template_service
+            .get_template::<TestRegisteredTemplate>(notification_id, channel_type)?;
+
+        Ok(template_service.render_template(template.0, context)?)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TestNotification {
+    pub id: usize,
+    pub message: String,
+}
+
+impl TestNotification {
+    pub fn new(id: usize, message: String) -> Self {
+        Self { id, message }
+    }
+}
+
+impl Notification for TestNotification {
+    type Id = &'static str;
+
+    fn id() -> Self::Id
+    where
+        Self: Sized,
+    {
+        "test_notification"
+    }
+}
+
+/// Start building a notifier with the test and failing channels and their
+/// templates for the test notification, for a test to add its own
+/// registrations and stores to.
+pub fn test_notifier(channel: TestChannel) -> NotifierBuilder<&'static str> {
+    Notifier::builder()
+        .channel(channel)
+        .channel(FailingChannel)
+        .template::<TestNotification, TestTemplate>(TestTemplate("message = {{message}}"))
+        .template::<TestNotification, FailingTemplate>(FailingTemplate("message = {{message}}"))
+}
@@ -0,0 +1,308 @@
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use async_trait::async_trait;
+
+use crate::{
+    message::Message,
+    provider::{Error, ErrorKind},
+    DeliveryReceipt, Provider,
+};
+
+/// Provider that records the messages it is sent instead of delivering them.
+/// Clones share the recorded messages, so keep a clone to make assertions
+/// after giving the provider to a channel.
+pub struct RecordingProvider<M> {
+    id: &'static str,
+    messages: Arc<Mutex<Vec<M>>>,
+}
+
+impl<M> RecordingProvider<M> {
+    pub fn new() -> Self {
+        Self {
+            id: "recording",
+            messages: Arc::default(),
+        }
+    }
+
+    /// Set the provider id used in the receipts.
+    pub fn with_id(mut self, id: &'static str) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// The number of messages recorded.
+    pub fn len(&self) -> usize {
+        self.messages.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove and return the recorded messages.
+    pub fn take(&self) -> Vec<M> {
+        std::mem::take(&mut *self.messages.lock().unwrap())
+    }
+
+    fn record(&self, message: M) -> DeliveryReceipt {
+        let mut messages = self.messages.lock().unwrap();
+        messages.push(message);
+
+        DeliveryReceipt::new(self.id).with_provider_message_id(messages.len().to_string())
+    }
+}
+
+impl<M: Clone> RecordingProvider<M> {
+    /// A copy of the recorded messages.
+    pub fn messages(&self) -> Vec<M> {
+        self.messages.lock().unwrap().clone()
+    }
+
+    /// A copy of the recorded messages that match the predicate.
+    pub fn matching(&self, predicate: impl Fn(&M) -> bool) -> Vec<M> {
+        self.messages
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|message| predicate(message))
+            .cloned()
+            .collect()
+    }
+}
+
+impl<M: Clone + Debug> RecordingProvider<M> {
+    /// Assert that exactly one recorded message matches the predicate and
+    /// return it. The description is used in the panic message.
+    #[track_caller]
+    pub fn assert_one(&self, description: &str, predicate: impl Fn(&M) -> bool) -> M {
+        let mut matching = self.matching(predicate);
+
+        if matching.len() != 1 {
+            panic!(
+                "expected exactly one message {description}, found {}. Recorded: {:#?}",
+                matching.len(),
+                self.messages()
+            );
+        }
+
+        matching.remove(0)
+    }
+
+    /// Assert that no recorded message matches the predicate.
+    #[track_caller]
+    pub fn assert_none(&self, description: &str, predicate: impl Fn(&M) -> bool) {
+        let matching = self.matching(predicate);
+
+        if !matching.is_empty() {
+            panic!("expected no messages {description}, found: {matching:#?}");
+        }
+    }
+
+    /// Assert the number of recorded messages.
+    #[track_caller]
+    pub fn assert_count(&self, count: usize) {
+        let messages = self.messages();
+
+        if messages.len() != count {
+            panic!(
+                "expected {count} messages, found {}: {messages:#?}",
+                messages.len()
+            );
+        }
+    }
+}
+
+impl<M> Default for RecordingProvider<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M> Clone for RecordingProvider<M> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            messages: Arc::clone(&self.messages),
+        }
+    }
+}
+
+#[async_trait]
+impl<M: Message> Provider for RecordingProvider<M> {
+    type Message = M;
+
+    fn id(&self) -> &'static str {
+        self.id
+    }
+
+    async fn send(&self, message: M) -> Result<DeliveryReceipt, Error> {
+        Ok(self.record(message))
+    }
+}
+
+/// Provider whose sends succeed or fail following a script, e.g. to test
+/// retries and failover. Once the script runs out every send succeeds, unless
+/// [`ScriptedProvider::fail_always`] was set. Successful sends are recorded.
+pub struct ScriptedProvider<M> {
+    script: Arc<Mutex<VecDeque<Option<ErrorKind>>>>,
+    otherwise: Option<ErrorKind>,
+    attempts: Arc<AtomicUsize>,
+    delivered: RecordingProvider<M>,
+}
+
+impl<M> ScriptedProvider<M> {
+    pub fn new() -> Self {
+        Self {
+            script: Arc::default(),
+            otherwise: None,
+            attempts: Arc::default(),
+            delivered: RecordingProvider::new().with_id("scripted"),
+        }
+    }
+
+    /// Add a send that fails with the kind of error to the script.
+    pub fn fail(self, kind: ErrorKind) -> Self {
+        self.script.lock().unwrap().push_back(Some(kind));
+        self
+    }
+
+    /// Add `times` sends that fail with the kind of error to the script.
+    pub fn fail_times(self, times: usize, kind: ErrorKind) -> Self {
+        (0..times).fold(self, |provider, _| provider.fail(kind))
+    }
+
+    /// Add a send that succeeds to the script.
+    pub fn succeed(self) -> Self {
+        self.script.lock().unwrap().push_back(None);
+        self
+    }
+
+    /// Fail every send once the script runs out.
+    pub fn fail_always(mut self, kind: ErrorKind) -> Self {
+        self.otherwise = Some(kind);
+        self
+    }
+
+    /// The number of sends attempted, successful or not.
+    pub fn attempts(&self) -> usize {
+        self.attempts.load(Ordering::SeqCst)
+    }
+
+    /// The messages that were sent successfully.
+    pub fn delivered(&self) -> &RecordingProvider<M> {
+        &self.delivered
+    }
+}
+
+impl<M> Default for ScriptedProvider<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M> Clone for ScriptedProvider<M> {
+    fn clone(&self) -> Self {
+        Self {
+            script: Arc::clone(&self.script),
+            otherwise: self.otherwise,
+            attempts: Arc::clone(&self.attempts),
+            delivered: self.delivered.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl<M: Message> Provider for ScriptedProvider<M> {
+    type Message = M;
+
+    fn id(&self) -> &'static str {
+        "scripted"
+    }
+
+    async fn send(&self, message: M) -> Result<DeliveryReceipt, Error> {
+        self.attempts.fetch_add(1, Ordering::SeqCst);
+
+        let step = self
+            .script
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or(self.otherwise);
+
+        match step {
+            None => Ok(self.delivered.record(message)),
+            Some(kind) => Err(Error::Send {
+                kind,
+                channel_id: "testing",
+                provider_id: "scripted",
+                source: anyhow::Error::msg("the scripted provider failed the send"),
+                context: None,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_testing_providers {
+    use super::*;
+    use crate::provider::{Retry, RetryPolicy};
+
+    #[tokio::test]
+    async fn test_recording_provider_assertions() {
+        let provider = RecordingProvider::<String>::new();
+
+        provider.send("hello alice".to_string()).await.unwrap();
+        provider.send("hello bob".to_string()).await.unwrap();
+
+        provider.assert_count(2);
+        assert_eq!(
+            provider.assert_one("to alice", |message| message.contains("alice")),
+            "hello alice"
+        );
+        provider.assert_none("to carol", |message| message.contains("carol"));
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "expected exactly one message saying hello, found 2")]
+    async fn test_recording_provider_assert_one_panics() {
+        let provider = RecordingProvider::<String>::new();
+
+        provider.send("hello alice".to_string()).await.unwrap();
+        provider.send("hello bob".to_string()).await.unwrap();
+
+        provider.assert_one("saying hello", |message| message.starts_with("hello"));
+    }
+
+    #[tokio::test]
+    async fn test_scripted_provider_with_retry() {
+        let provider = ScriptedProvider::<String>::new().fail_times(2, ErrorKind::Transient);
+        let retry = Retry::new(
+            provider.clone(),
+            RetryPolicy::new()
+                .with_max_attempts(3)
+                .with_initial_backoff(std::time::Duration::from_millis(1)),
+        );
+
+        retry.send("hello".to_string()).await.unwrap();
+
+        assert_eq!(provider.attempts(), 3);
+        provider.delivered().assert_count(1);
+
+        let failing = ScriptedProvider::<String>::new()
+            .succeed()
+            .fail_always(ErrorKind::Permanent);
+
+        assert!(failing.send("first".to_string()).await.is_ok());
+        assert_eq!(
+            failing.send("second".to_string()).await.unwrap_err().kind(),
+            ErrorKind::Permanent
+        );
+    }
+}